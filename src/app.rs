@@ -1,7 +1,10 @@
 pub mod calendar;
 pub mod options;
 pub mod projects;
+pub mod report;
+pub mod session_edit;
 pub mod sessions;
+pub mod tasks;
 
 use color_eyre;
 use ratatui::{
@@ -10,15 +13,18 @@ use ratatui::{
   widgets::ListState,
 };
 
+use crate::daemon::{Request, client::DaemonClient};
 use crate::repository::Repository;
 use crate::structs::{
-  App, CalendarSection, CalendarState, ProjectsList, State, UserConfig,
+  App, CalendarSection, CalendarState, DaySummary, ProjectsList, ReportState,
+  State, UserConfig,
 };
 use crate::tui;
 use crate::utils;
 use crate::widgets::{
-  CalendarWidget, ConfirmWidget, CounterWidget, InputWidget, OptionsWidget,
-  ProjectsListWidget,
+  CalendarWidget, ConfirmWidget, CounterWidget, DeleteProjectWidget,
+  EstimatesReportWidget, InputWidget, OptionsWidget, ProjectsListWidget,
+  RecoverWidget, ReportWidget,
 };
 use options::{Options, OptionsState};
 use std::time::Duration;
@@ -37,41 +43,81 @@ impl App {
       Some(proj) => Some(proj.id),
       None => None,
     };
+    let selected_task = repo
+      .get_selected_task()
+      .unwrap_or_default()
+      .filter(|task| Some(task.project_id) == selected_id);
     let options = repo.create_of_get_options().unwrap_or(Options {
       id: 1,
       work_duration: 25,
       break_duration: 5,
       ask_before_work: false,
       ask_before_break: false,
+      long_break_duration: 15,
+      sessions_before_long_break: 4,
+      heatmap_threshold: 120,
     });
 
+    let mut daemon = DaemonClient::connect();
+    let (current_session, state) = match daemon.as_mut() {
+      Some(client) => {
+        let session = client
+          .request(&Request::Status)
+          .map_or(None, |response| response.session);
+        let state = sessions::get_session_state(session.as_ref());
+        (session, state)
+      }
+      // A session still saved means tomato was closed before it ended
+      None => match repo.get_active_session().unwrap_or(None) {
+        Some(session) => (Some(session), State::ConfirmRecover),
+        None => (None, State::None),
+      },
+    };
+
     App {
-      state: State::None,
+      state,
       exit: false,
-      current_session: None,
+      current_session,
+      daemon,
       input: String::new(),
       repo,
       projects_list: ProjectsList {
         projects,
         selected_id,
         state: ListState::default(),
+        show_finished: false,
+        deletion: None,
+        tag_filter: None,
+        tasks: vec![],
+        task_state: ListState::default(),
+        tasks_focused: false,
+        selected_task,
       },
       calendar: CalendarState {
         selected_date: None,
         sessions: vec![],
+        summary: DaySummary::default(),
+        month_totals: vec![],
+        week_totals: vec![],
+        expanded: None,
+        expanded_sessions: vec![],
+        tag_filter: None,
+        draft: None,
         list_state: ListState::default(),
         selected_section: CalendarSection::Calendar,
       },
+      report: ReportState::default(),
       options: OptionsState {
         data: options,
         selected_index: 0,
-        options_number: 4,
+        options_number: 7,
       },
     }
   }
 
   pub fn run(&mut self, terminal: &mut tui::Tui) -> color_eyre::Result<()> {
     while !self.exit {
+      self.sync_with_daemon();
       terminal.draw(|frame| {
         self.render_layout(frame);
       })?;
@@ -89,27 +135,45 @@ impl App {
     match &self.state {
       State::WorkSession => {
         let session = self.current_session.as_ref().unwrap();
-        let time = utils::render_timer_str(session.start, session.duration);
+        let time = utils::render_timer_str(
+          session.start,
+          session.duration,
+          session.get_paused_time(),
+        );
         if time.is_none() {
-          self.toggle_session();
+          // The daemon ends sessions itself
+          if self.daemon.is_none() {
+            self.toggle_session();
+          }
           return;
         }
         let counter_widget = CounterWidget {
           time: time.unwrap(),
           session_type: session.session_type,
+          paused: session.is_paused(),
+          cycle: self.get_cycle_position(),
         };
         frame.render_widget(counter_widget, frame.area());
       }
       State::BreakSession => {
         let session = self.current_session.as_ref().unwrap();
-        let time = utils::render_timer_str(session.start, session.duration);
+        let time = utils::render_timer_str(
+          session.start,
+          session.duration,
+          session.get_paused_time(),
+        );
         if time.is_none() {
-          self.toggle_session();
+          // The daemon ends sessions itself
+          if self.daemon.is_none() {
+            self.toggle_session();
+          }
           return;
         }
         let counter_widget = CounterWidget {
           time: time.unwrap(),
           session_type: session.session_type,
+          paused: session.is_paused(),
+          cycle: self.get_cycle_position(),
         };
         frame.render_widget(counter_widget, frame.area());
       }
@@ -129,10 +193,29 @@ impl App {
       ),
       State::ConfirmFinished => frame.render_widget(
         ConfirmWidget {
-          question: String::from(" Finish Project ? "),
+          question: String::from(if self.projects_list.show_finished {
+            " Unfinish Project ? "
+          } else {
+            " Finish Project ? "
+          }),
         },
         frame.area(),
       ),
+      State::ConfirmRecover => {
+        let session = self.current_session.as_ref().unwrap();
+        let time = utils::render_timer_str(
+          session.start,
+          session.duration,
+          session.get_paused_time(),
+        );
+        frame.render_widget(
+          RecoverWidget {
+            session_type: session.session_type,
+            time,
+          },
+          frame.area(),
+        );
+      }
 
       State::WorkInput => frame.render_widget(
         InputWidget {
@@ -155,6 +238,75 @@ impl App {
           projects: &self.projects_list.projects,
           selected_id: self.projects_list.selected_id,
           state: &mut self.projects_list.state,
+          picker: false,
+          finished: self.projects_list.show_finished,
+          tag_filter: self.projects_list.tag_filter.as_deref(),
+          tasks: &self.projects_list.tasks,
+          task_index: if self.projects_list.tasks_focused {
+            self.projects_list.task_state.selected()
+          } else {
+            None
+          },
+          selected_task_id: self
+            .projects_list
+            .selected_task
+            .as_ref()
+            .map(|task| task.id),
+        },
+        frame.area(),
+      ),
+      State::SessionProjectInput => frame.render_widget(
+        ProjectsListWidget {
+          projects: &self.projects_list.projects,
+          selected_id: self.projects_list.selected_id,
+          state: &mut self.projects_list.state,
+          picker: true,
+          finished: false,
+          tag_filter: None,
+          tasks: &[],
+          task_index: None,
+          selected_task_id: None,
+        },
+        frame.area(),
+      ),
+      State::ConfirmDeleteProject => {
+        if let Some(deletion) = &self.projects_list.deletion {
+          frame.render_widget(DeleteProjectWidget { deletion }, frame.area())
+        }
+      }
+      State::ReassignProjectInput => frame.render_widget(
+        ProjectsListWidget {
+          projects: &self.projects_list.projects,
+          selected_id: self.projects_list.selected_id,
+          state: &mut self.projects_list.state,
+          picker: true,
+          finished: false,
+          tag_filter: None,
+          tasks: &[],
+          task_index: None,
+          selected_task_id: None,
+        },
+        frame.area(),
+      ),
+      State::SessionStartInput => frame.render_widget(
+        InputWidget {
+          title: " Start (HH:MM) ",
+          width: 25,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::SessionDurationInput => frame.render_widget(
+        InputWidget {
+          title: " Duration (minutes) ",
+          width: 25,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::ConfirmDeleteSession => frame.render_widget(
+        ConfirmWidget {
+          question: String::from(" Delete Session ? "),
         },
         frame.area(),
       ),
@@ -174,12 +326,81 @@ impl App {
         },
         frame.area(),
       ),
+      State::TaskInputAdd => frame.render_widget(
+        InputWidget {
+          title: " Add Task ",
+          width: 50,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::TaskInputUpdate => frame.render_widget(
+        InputWidget {
+          title: " Update Task ",
+          width: 50,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::ProjectsInputEstimate | State::TaskInputEstimate => frame
+        .render_widget(
+          InputWidget {
+            title: " Estimated Pomodoros ",
+            width: 30,
+            input: &self.input,
+          },
+          frame.area(),
+        ),
+      State::ProjectsInputDurations => frame.render_widget(
+        InputWidget {
+          title: " Work/Break/Long Break Minutes ",
+          width: 40,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::ProjectsInputTags => frame.render_widget(
+        InputWidget {
+          title: " Tags, separated by commas ",
+          width: 50,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
       State::Calendar => frame.render_widget(
         CalendarWidget {
           selected_date: self.calendar.selected_date.unwrap(),
           sessions: &self.calendar.sessions,
+          summary: &self.calendar.summary,
+          month_totals: &self.calendar.month_totals,
+          week_totals: &self.calendar.week_totals,
+          expanded: self.calendar.expanded,
+          expanded_sessions: &self.calendar.expanded_sessions,
+          heatmap_threshold: self.options.data.heatmap_threshold,
           list_state: &mut self.calendar.list_state,
           selected_section: &self.calendar.selected_section,
+          tag_filter: self.calendar.tag_filter.as_deref(),
+        },
+        frame.area(),
+      ),
+      State::ExportInput => frame.render_widget(
+        InputWidget {
+          title: " Export Sessions To ",
+          width: 60,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::Report => frame.render_widget(
+        ReportWidget {
+          report: &self.report,
+        },
+        frame.area(),
+      ),
+      State::EstimatesReport => frame.render_widget(
+        EstimatesReportWidget {
+          projects: &self.report.estimates,
+          tag_filter: self.report.tag_filter.as_deref(),
         },
         frame.area(),
       ),
@@ -206,6 +427,30 @@ impl App {
         },
         frame.area(),
       ),
+      State::LongBreakDurationInput => frame.render_widget(
+        InputWidget {
+          title: " Long Break Duration: ",
+          width: 25,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::SessionsBeforeLongBreakInput => frame.render_widget(
+        InputWidget {
+          title: " Sessions Before Long Break: ",
+          width: 32,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
+      State::HeatmapThresholdInput => frame.render_widget(
+        InputWidget {
+          title: " Heatmap Threshold (minutes): ",
+          width: 34,
+          input: &self.input,
+        },
+        frame.area(),
+      ),
       _ => {}
     }
   }
@@ -218,15 +463,41 @@ impl App {
           State::WorkInput | State::BreakInput => {
             self.handle_num_input(key_event)
           }
-          State::WorkDurationInput | State::BreakDurationInput => {
-            self.handle_num_input(key_event)
-          }
+          State::WorkDurationInput
+          | State::BreakDurationInput
+          | State::LongBreakDurationInput
+          | State::SessionsBeforeLongBreakInput
+          | State::HeatmapThresholdInput => self.handle_num_input(key_event),
           State::ProjectsList => self.handle_projects_list_input(key_event),
-          State::ProjectsInputAdd | State::ProjectsInputUpdate => {
-            self.handle_project_input(key_event)
+          State::ProjectsInputAdd
+          | State::ProjectsInputUpdate
+          | State::ProjectsInputTags
+          | State::ProjectsInputEstimate
+          | State::ProjectsInputDurations
+          | State::TaskInputAdd
+          | State::TaskInputUpdate
+          | State::TaskInputEstimate => self.handle_project_input(key_event),
+          State::ConfirmDeleteProject => {
+            self.handle_delete_project_input(key_event)
           }
+          State::ReassignProjectInput => self.handle_reassign_input(key_event),
           State::Calendar => self.handle_calendar_input(key_event),
+          State::ExportInput => self.handle_export_input(key_event),
+          State::Report => self.handle_report_input(key_event),
+          State::EstimatesReport => {
+            self.handle_estimates_report_input(key_event)
+          }
+          State::SessionProjectInput => {
+            self.handle_session_project_input(key_event)
+          }
+          State::SessionStartInput | State::SessionDurationInput => {
+            self.handle_session_input(key_event)
+          }
+          State::ConfirmDeleteSession => {
+            self.handle_delete_session_input(key_event)
+          }
           State::Options => self.handler_options_input(key_event),
+          State::ConfirmRecover => self.handle_recover_input(key_event),
           _ => self.handle_key_event(key_event),
         }
       }
@@ -261,8 +532,7 @@ impl App {
       },
       // For now we can check projects only when not in a session
       KeyCode::Char('p') => match self.state {
-        State::BreakSession => {}
-        State::WorkSession => {}
+        State::BreakSession | State::WorkSession => self.toggle_pause(),
         _ => {
           self.list_projects();
         }
@@ -274,6 +544,13 @@ impl App {
           self.display_calendar();
         }
       },
+      KeyCode::Char('r') => match self.state {
+        State::BreakSession => {}
+        State::WorkSession => {}
+        _ => {
+          self.display_report();
+        }
+      },
       KeyCode::Char('o') => match self.state {
         State::BreakSession => {}
         State::WorkSession => {}
@@ -316,6 +593,36 @@ impl App {
           self.input.clear();
           self.state = State::Options;
         }
+        State::LongBreakDurationInput => {
+          if let Ok(val) = self.input.parse::<u32>() {
+            self.options.data.long_break_duration = val;
+            if self.repo.update_options(self.options.data.clone()).is_err() {
+              utils::notify("Error saving long break duration");
+            }
+          }
+          self.input.clear();
+          self.state = State::Options;
+        }
+        State::SessionsBeforeLongBreakInput => {
+          if let Ok(val) = self.input.parse::<u32>() {
+            self.options.data.sessions_before_long_break = val;
+            if self.repo.update_options(self.options.data.clone()).is_err() {
+              utils::notify("Error saving sessions before long break");
+            }
+          }
+          self.input.clear();
+          self.state = State::Options;
+        }
+        State::HeatmapThresholdInput => {
+          if let Ok(val) = self.input.parse::<u32>() {
+            self.options.data.heatmap_threshold = val;
+            if self.repo.update_options(self.options.data.clone()).is_err() {
+              utils::notify("Error saving heatmap threshold");
+            }
+          }
+          self.input.clear();
+          self.state = State::Options;
+        }
         _ => {}
       },
       KeyCode::Char(char) => {
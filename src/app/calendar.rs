@@ -1,4 +1,8 @@
-use crate::structs::{App, CalendarSection, State};
+use crate::export;
+use crate::structs::{
+  App, CalendarRow, CalendarSection, DaySummary, ProjectFilter, SessionFilter,
+  State,
+};
 use crate::utils;
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
 use time::{Date, Duration, OffsetDateTime};
@@ -38,6 +42,23 @@ impl App {
         }
       }
       KeyCode::Tab => self.switch_cal_section(),
+      KeyCode::Enter => {
+        if let CalendarSection::List = self.calendar.selected_section {
+          self.toggle_expanded_entry();
+        }
+      }
+      KeyCode::Char('e') => self.start_export_input(),
+      KeyCode::Char('#') => {
+        self.calendar.tag_filter =
+          self.get_next_tag(self.calendar.tag_filter.as_deref());
+        self.set_date_and_sessions(self.calendar.selected_date);
+      }
+      KeyCode::Char('a') => self.start_adding_session(),
+      KeyCode::Char('u') => self.start_editing_duration(),
+      KeyCode::Char('m') => self.start_moving_session(),
+      KeyCode::Char('d') if self.get_highlighted_session().is_some() => {
+        self.state = State::ConfirmDeleteSession;
+      }
       KeyCode::Char('p') => {
         self.reset_calendar();
         self.list_projects()
@@ -46,6 +67,85 @@ impl App {
     }
   }
 
+  pub fn handle_export_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char(char) => self.input.push(char),
+      KeyCode::Backspace => {
+        self.input.pop();
+      }
+      KeyCode::Enter => {
+        self.export_month();
+        self.input.clear();
+        self.state = State::Calendar;
+      }
+      KeyCode::Esc => {
+        self.input.clear();
+        self.state = State::Calendar;
+      }
+      _ => {}
+    }
+  }
+
+  /// Asks where to export the sessions of the month shown, the path
+  /// extension picks CSV or JSON
+  fn start_export_input(&mut self) {
+    let Some(date) = self.calendar.selected_date else {
+      return;
+    };
+    let mut path = dirs::home_dir().unwrap_or_default();
+    path.push(format!(
+      "tomato-{}-{:02}.csv",
+      date.year(),
+      date.month() as u8
+    ));
+    self.input = path.to_string_lossy().into_owned();
+    self.state = State::ExportInput;
+  }
+
+  /// Exports the month shown, only the highlighted project when the list is
+  /// selected
+  fn export_month(&mut self) {
+    let Some(date) = self.calendar.selected_date else {
+      return;
+    };
+    let project = match self.calendar.selected_section {
+      CalendarSection::Calendar => ProjectFilter::All,
+      CalendarSection::List => {
+        let entry = match self.calendar.get_selected_row() {
+          Some(CalendarRow::Project(entry)) => Some(entry),
+          Some(CalendarRow::Session(_)) => self.calendar.expanded,
+          None => None,
+        };
+        // Unassigned sessions only, not the whole month
+        match entry.and_then(|entry| self.calendar.sessions.get(entry)) {
+          Some(session) => match session.project_id {
+            Some(id) => ProjectFilter::Project(id),
+            None => ProjectFilter::Unassigned,
+          },
+          None => return utils::notify("No project to export"),
+        }
+      }
+    };
+    let (first_day, last_day) = utils::get_month_bounds(date);
+    let filter = SessionFilter {
+      from: Some(first_day),
+      to: Some(last_day),
+      project,
+      tag: self.calendar.tag_filter.clone(),
+    };
+
+    let zone = utils::LocalZone;
+    match export::export_sessions_to_file(
+      &self.repo,
+      &filter,
+      zone,
+      &self.input,
+    ) {
+      Ok(()) => utils::notify(&format!("Sessions exported to {}", self.input)),
+      Err(err) => utils::notify(&format!("Export failed: {}", err)),
+    }
+  }
+
   pub fn prev_day(&mut self) {
     match OffsetDateTime::now_local() {
       Ok(offset) => {
@@ -107,19 +207,104 @@ impl App {
     self.state = State::Calendar;
   }
 
-  fn set_date_and_sessions(&mut self, date: Option<Date>) {
+  pub fn set_date_and_sessions(&mut self, date: Option<Date>) {
     self.calendar.selected_date = date;
+    self.collapse_entry();
     if date.is_none() {
       self.calendar.sessions = vec![];
     }
 
-    match self.repo.get_sessions_per_day(&date.unwrap()) {
+    let zone = utils::LocalZone;
+    match self.repo.get_sessions_per_day(
+      &date.unwrap(),
+      zone,
+      self.calendar.tag_filter.as_deref(),
+    ) {
       Ok(sessions) => self.calendar.sessions = sessions,
       Err(err) => {
         utils::notify(&err.to_string());
         self.calendar.sessions = vec![]
       }
     }
+
+    match self.repo.get_day_summary(
+      &date.unwrap(),
+      zone,
+      self.calendar.tag_filter.as_deref(),
+    ) {
+      Ok(summary) => self.calendar.summary = summary,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        self.calendar.summary = DaySummary::default()
+      }
+    }
+
+    let (first_day, last_day) = utils::get_month_bounds(date.unwrap());
+    let filter = SessionFilter {
+      from: Some(first_day),
+      to: Some(last_day),
+      project: ProjectFilter::All,
+      tag: self.calendar.tag_filter.clone(),
+    };
+    match self.repo.get_day_totals(&filter, zone) {
+      Ok(totals) => self.calendar.month_totals = totals,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        self.calendar.month_totals = vec![]
+      }
+    }
+
+    let (monday, sunday) = utils::get_week_bounds(date.unwrap());
+    let filter = SessionFilter {
+      from: Some(monday),
+      to: Some(sunday),
+      project: ProjectFilter::All,
+      tag: self.calendar.tag_filter.clone(),
+    };
+    match self.repo.get_day_project_totals(&filter, zone) {
+      Ok(totals) => self.calendar.week_totals = totals,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        self.calendar.week_totals = vec![]
+      }
+    }
+  }
+
+  /// Shows the sessions of the highlighted project entry, or hides them
+  fn toggle_expanded_entry(&mut self) {
+    let Some(CalendarRow::Project(entry)) = self.calendar.get_selected_row()
+    else {
+      return;
+    };
+    if self.calendar.expanded == Some(entry) {
+      self.collapse_entry();
+    } else {
+      self.expand_entry(entry);
+    }
+    self.calendar.list_state.select(Some(entry));
+  }
+
+  pub fn expand_entry(&mut self, entry: usize) {
+    self.collapse_entry();
+    let (Some(date), Some(project)) = (
+      self.calendar.selected_date,
+      self.calendar.sessions.get(entry),
+    ) else {
+      return;
+    };
+    let zone = utils::LocalZone;
+    match self.repo.get_day_sessions(&date, zone, project.project_id) {
+      Ok(sessions) => {
+        self.calendar.expanded = Some(entry);
+        self.calendar.expanded_sessions = sessions;
+      }
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  fn collapse_entry(&mut self) {
+    self.calendar.expanded = None;
+    self.calendar.expanded_sessions = vec![];
   }
 
   fn switch_cal_section(&mut self) {
@@ -139,7 +324,7 @@ impl App {
     let i = match self.calendar.list_state.selected() {
       None => 0,
       Some(index) => {
-        if index >= self.calendar.sessions.len() - 1 {
+        if index + 1 >= self.calendar.get_rows_count() {
           0
         } else {
           index + 1
@@ -154,7 +339,7 @@ impl App {
       None => 0,
       Some(index) => {
         if index == 0 {
-          self.calendar.sessions.len() - 1
+          self.calendar.get_rows_count().saturating_sub(1)
         } else {
           index - 1
         }
@@ -168,5 +353,11 @@ impl App {
     self.calendar.selected_section = CalendarSection::Calendar;
     self.calendar.list_state.select(Some(0));
     self.calendar.sessions = vec![];
+    self.calendar.summary = DaySummary::default();
+    self.calendar.month_totals = vec![];
+    self.calendar.week_totals = vec![];
+    self.calendar.draft = None;
+    self.calendar.tag_filter = None;
+    self.collapse_entry();
   }
 }
@@ -62,6 +62,18 @@ impl App {
           self.input = self.options.data.break_duration.to_string();
           self.state = State::BreakDurationInput;
         }
+        OptionField::LongBreakDuration => {
+          self.input = self.options.data.long_break_duration.to_string();
+          self.state = State::LongBreakDurationInput;
+        }
+        OptionField::SessionsBeforeLongBreak => {
+          self.input = self.options.data.sessions_before_long_break.to_string();
+          self.state = State::SessionsBeforeLongBreakInput;
+        }
+        OptionField::HeatmapThreshold => {
+          self.input = self.options.data.heatmap_threshold.to_string();
+          self.state = State::HeatmapThresholdInput;
+        }
         _ => {}
       },
       BoolOrInt::Bool(val) => {
@@ -88,6 +100,10 @@ pub struct Options {
   pub break_duration: u32,
   pub ask_before_work: bool,
   pub ask_before_break: bool,
+  pub long_break_duration: u32,
+  pub sessions_before_long_break: u32,
+  /// Minutes of focus for the strongest color of the calendar
+  pub heatmap_threshold: u32,
 }
 
 // UI navigation enum
@@ -95,6 +111,9 @@ pub struct Options {
 pub enum OptionField {
   WorkDuration,
   BreakDuration,
+  LongBreakDuration,
+  SessionsBeforeLongBreak,
+  HeatmapThreshold,
   AskBeforeWork,
   AskBeforeBreak,
 }
@@ -106,7 +125,7 @@ pub enum BoolOrInt {
 }
 
 impl Options {
-  pub fn get_list(&self) -> [(OptionField, String, String); 4] {
+  pub fn get_list(&self) -> [(OptionField, String, String); 7] {
     [
       (
         OptionField::WorkDuration,
@@ -118,6 +137,21 @@ impl Options {
         String::from("Break duration"),
         self.break_duration.to_string(),
       ),
+      (
+        OptionField::LongBreakDuration,
+        String::from("Long break duration"),
+        self.long_break_duration.to_string(),
+      ),
+      (
+        OptionField::SessionsBeforeLongBreak,
+        String::from("Work sessions before long break"),
+        self.sessions_before_long_break.to_string(),
+      ),
+      (
+        OptionField::HeatmapThreshold,
+        String::from("Calendar heatmap threshold"),
+        self.heatmap_threshold.to_string(),
+      ),
       (
         OptionField::AskBeforeWork,
         String::from("Ask time before work session"),
@@ -135,6 +169,13 @@ impl Options {
     match field {
       OptionField::WorkDuration => BoolOrInt::Int(self.work_duration),
       OptionField::BreakDuration => BoolOrInt::Int(self.break_duration),
+      OptionField::LongBreakDuration => {
+        BoolOrInt::Int(self.long_break_duration)
+      }
+      OptionField::SessionsBeforeLongBreak => {
+        BoolOrInt::Int(self.sessions_before_long_break)
+      }
+      OptionField::HeatmapThreshold => BoolOrInt::Int(self.heatmap_threshold),
       OptionField::AskBeforeWork => BoolOrInt::Bool(self.ask_before_work),
       OptionField::AskBeforeBreak => BoolOrInt::Bool(self.ask_before_break),
     }
@@ -152,6 +193,21 @@ impl Options {
           self.break_duration = v;
         }
       }
+      OptionField::LongBreakDuration => {
+        if let BoolOrInt::Int(v) = value {
+          self.long_break_duration = v;
+        }
+      }
+      OptionField::SessionsBeforeLongBreak => {
+        if let BoolOrInt::Int(v) = value {
+          self.sessions_before_long_break = v;
+        }
+      }
+      OptionField::HeatmapThreshold => {
+        if let BoolOrInt::Int(v) = value {
+          self.heatmap_threshold = v;
+        }
+      }
       OptionField::AskBeforeWork => {
         if let BoolOrInt::Bool(v) = value {
           self.ask_before_work = v;
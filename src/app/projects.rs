@@ -1,22 +1,54 @@
-use crate::structs::{App, Project, State};
+use crate::structs::{App, Project, ProjectDeletion, State};
 use crate::utils;
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
 
 impl App {
   pub fn handle_projects_list_input(&mut self, key_event: KeyEvent) {
+    if self.projects_list.tasks_focused {
+      return self.handle_tasks_input(key_event);
+    }
     match key_event.code {
       KeyCode::Char('q') => self.exit(),
+      KeyCode::Right | KeyCode::Char('l') => self.focus_tasks(),
       KeyCode::Down | KeyCode::Char('j') => self.next_project(),
       KeyCode::Up | KeyCode::Char('k') => self.prev_project(),
       KeyCode::Char('p') | KeyCode::Esc => {
+        self.reset_projects_view();
         self.state = State::None;
       }
-      KeyCode::Char('a') => {
+      KeyCode::Tab => self.show_projects(!self.projects_list.show_finished),
+      KeyCode::Char('a') if !self.projects_list.show_finished => {
         self.state = State::ProjectsInputAdd;
       }
       KeyCode::Char('f') => {
         self.state = State::ConfirmFinished;
       }
+      KeyCode::Char('d') => self.start_project_deletion(),
+      KeyCode::Char('t') => {
+        if let Some(project) = self.get_highlighted_project() {
+          self.input = project.tags.join(", ");
+          self.state = State::ProjectsInputTags;
+        }
+      }
+      KeyCode::Char('e') => {
+        if let Some(project) = self.get_highlighted_project() {
+          self.input =
+            project.estimate.map(|e| e.to_string()).unwrap_or_default();
+          self.state = State::ProjectsInputEstimate;
+        }
+      }
+      KeyCode::Char('o') if !self.projects_list.show_finished => {
+        if let Some(project) = self.get_highlighted_project() {
+          self.input = utils::render_durations(project.durations);
+          self.state = State::ProjectsInputDurations;
+        }
+      }
+      KeyCode::Char('#') => {
+        self.projects_list.tag_filter =
+          self.get_next_tag(self.projects_list.tag_filter.as_deref());
+        self.projects_list.state.select(Some(0));
+        self.get_projects();
+      }
       KeyCode::Char('u') => {
         let value = match self.get_highlighted_project() {
           Some(project) => project.name.clone(),
@@ -27,9 +59,10 @@ impl App {
         self.state = State::ProjectsInputUpdate;
       }
       KeyCode::Char('c') => {
+        self.reset_projects_view();
         self.display_calendar();
       }
-      KeyCode::Char(' ') => {
+      KeyCode::Char(' ') if !self.projects_list.show_finished => {
         let selected_index = self.projects_list.state.selected();
         match selected_index {
           Some(index) => match self.projects_list.projects.get(index) {
@@ -68,6 +101,36 @@ impl App {
           self.input = String::new();
           self.state = State::ProjectsList;
         }
+        State::ProjectsInputTags => {
+          self.update_project_tags();
+          self.input = String::new();
+          self.state = State::ProjectsList;
+        }
+        State::ProjectsInputEstimate => {
+          self.update_project_estimate();
+          self.input = String::new();
+          self.state = State::ProjectsList;
+        }
+        State::ProjectsInputDurations => {
+          self.update_project_durations();
+          self.input = String::new();
+          self.state = State::ProjectsList;
+        }
+        State::TaskInputAdd => {
+          self.add_task();
+          self.input = String::new();
+          self.state = State::ProjectsList;
+        }
+        State::TaskInputUpdate => {
+          self.update_task();
+          self.input = String::new();
+          self.state = State::ProjectsList;
+        }
+        State::TaskInputEstimate => {
+          self.update_task_estimate();
+          self.input = String::new();
+          self.state = State::ProjectsList;
+        }
         _ => {}
       },
       KeyCode::Esc => {
@@ -78,6 +141,94 @@ impl App {
     }
   }
 
+  pub fn handle_delete_project_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Char('y') => {
+        if let Some(deletion) = self.projects_list.deletion.take() {
+          let result = self.repo.delete_project(deletion.project_id);
+          self.after_project_deletion(deletion.project_id, result);
+        }
+        self.state = State::ProjectsList;
+      }
+      KeyCode::Char('r') => {
+        self.reset_projects_view();
+        self.state = State::ReassignProjectInput;
+      }
+      KeyCode::Char('n') | KeyCode::Esc => {
+        self.projects_list.deletion = None;
+        self.state = State::ProjectsList;
+      }
+      _ => {}
+    }
+  }
+
+  /// Picks the project getting the sessions of the one deleted
+  pub fn handle_reassign_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Down | KeyCode::Char('j') => self.next_project(),
+      KeyCode::Up | KeyCode::Char('k') => self.prev_project(),
+      KeyCode::Enter => {
+        let project_id = self.get_highlighted_project().map(|p| p.id);
+        self.reassign_and_delete_project(project_id);
+      }
+      KeyCode::Char('n') => self.reassign_and_delete_project(None),
+      KeyCode::Esc => self.state = State::ConfirmDeleteProject,
+      _ => {}
+    }
+  }
+
+  fn start_project_deletion(&mut self) {
+    let Some(project) = self.get_highlighted_project() else {
+      return;
+    };
+    let (project_id, name) = (project.id, project.name.clone());
+    match self.repo.get_project_usage(project_id) {
+      Ok((sessions, duration)) => {
+        self.projects_list.deletion = Some(ProjectDeletion {
+          project_id,
+          name,
+          sessions,
+          duration,
+        });
+        self.state = State::ConfirmDeleteProject;
+      }
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  fn reassign_and_delete_project(&mut self, into: Option<usize>) {
+    let Some(deletion) = &self.projects_list.deletion else {
+      return;
+    };
+    let project_id = deletion.project_id;
+    if into == Some(project_id) {
+      utils::notify("Choose another project for the sessions");
+      return;
+    }
+    let result = self.repo.merge_project(project_id, into);
+    self.projects_list.deletion = None;
+    self.after_project_deletion(project_id, result);
+    self.state = State::ProjectsList;
+  }
+
+  fn after_project_deletion(
+    &mut self,
+    project_id: usize,
+    result: Result<(), rusqlite::Error>,
+  ) {
+    if let Err(err) = result {
+      utils::notify(&err.to_string());
+      return;
+    }
+    if self.projects_list.selected_id == Some(project_id) {
+      self.projects_list.selected_id = None;
+    }
+    self.sync_selected_task();
+    self.get_projects();
+  }
+
   pub fn add_project(&mut self) {
     let trimmed = self.input.trim();
     if trimmed.is_empty() {
@@ -102,9 +253,94 @@ impl App {
     }
   }
 
+  /// Tags are separated by commas, a leading # is left out
+  pub fn update_project_tags(&mut self) {
+    let Some(project) = self.get_highlighted_project() else {
+      return;
+    };
+    let id = project.id;
+    let mut tags: Vec<String> = vec![];
+    for tag in self.input.split(',') {
+      let tag = tag.trim().trim_start_matches('#').trim();
+      if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+        tags.push(tag.to_string());
+      }
+    }
+    match self.repo.set_project_tags(id, &tags) {
+      Ok(()) => self.get_projects(),
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  pub fn update_project_estimate(&mut self) {
+    let Some(id) = self.get_highlighted_project().map(|p| p.id) else {
+      return;
+    };
+    let estimate = match utils::parse_estimate(&self.input) {
+      Ok(estimate) => estimate,
+      Err(err) => return utils::notify(err),
+    };
+    match self.repo.set_project_estimate(id, estimate) {
+      Ok(()) => self.get_projects(),
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  pub fn update_project_durations(&mut self) {
+    let Some(id) = self.get_highlighted_project().map(|p| p.id) else {
+      return;
+    };
+    let durations = match utils::parse_durations(&self.input) {
+      Ok(durations) => durations,
+      Err(err) => return utils::notify(err),
+    };
+    match self.repo.set_project_durations(id, durations) {
+      Ok(()) => self.get_projects(),
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  /// Tag filters go through every tag then back to none
+  pub fn get_next_tag(&self, tag: Option<&str>) -> Option<String> {
+    let tags = match self.repo.get_tags() {
+      Ok(tags) => tags,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        return None;
+      }
+    };
+    let next = match tag {
+      None => 0,
+      Some(tag) => {
+        match tags.iter().position(|t| t.eq_ignore_ascii_case(tag)) {
+          Some(index) => index + 1,
+          None => 0,
+        }
+      }
+    };
+    tags.into_iter().nth(next)
+  }
+
   pub fn get_projects(&mut self) {
-    match self.repo.get_projects_in_progress() {
-      Ok(projects) => self.projects_list.projects = projects,
+    let projects = if self.projects_list.show_finished {
+      self.repo.get_finished_projects()
+    } else {
+      self.repo.get_projects_in_progress()
+    };
+    match projects {
+      Ok(mut projects) => {
+        if let Some(tag) = &self.projects_list.tag_filter {
+          projects.retain(|project| {
+            project.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+          });
+        }
+        let last = projects.len().saturating_sub(1);
+        if let Some(index) = self.projects_list.state.selected() {
+          self.projects_list.state.select(Some(index.min(last)));
+        }
+        self.projects_list.projects = projects;
+        self.load_tasks();
+      }
       Err(err) => {
         println!("err: {:?}", err);
       }
@@ -134,16 +370,25 @@ impl App {
         }
       }
     }
+    self.sync_selected_task();
   }
 
+  /// Finishes the highlighted project, or brings it back in progress from
+  /// the finished ones
   pub fn finish_project(&mut self) {
     match self.get_highlighted_project() {
       Some(project) => {
-        match self.repo.mark_project_finished(project.id.clone()) {
+        let id = project.id;
+        let result = if self.projects_list.show_finished {
+          self.repo.mark_project_unfinished(id)
+        } else {
+          self.repo.mark_project_finished(id)
+        };
+        match result {
           Ok(()) => {
             self.get_projects();
           }
-          Err(_) => {}
+          Err(err) => utils::notify(&err.to_string()),
         }
       }
       None => {}
@@ -158,7 +403,7 @@ impl App {
     self.projects_list.projects.get(highlighted_index)
   }
 
-  pub fn get_selected_project(&mut self) -> Option<&Project> {
+  pub fn get_selected_project(&self) -> Option<&Project> {
     match self.projects_list.selected_id {
       None => None,
       Some(id) => self
@@ -170,6 +415,9 @@ impl App {
   }
 
   pub fn next_project(&mut self) {
+    if self.projects_list.projects.is_empty() {
+      return;
+    }
     let i = match self.projects_list.state.selected() {
       None => 0,
       Some(index) => {
@@ -181,9 +429,14 @@ impl App {
       }
     };
     self.projects_list.state.select(Some(i));
+    self.projects_list.task_state.select(Some(0));
+    self.load_tasks();
   }
 
   pub fn prev_project(&mut self) {
+    if self.projects_list.projects.is_empty() {
+      return;
+    }
     let i = match self.projects_list.state.selected() {
       None => 0,
       Some(index) => {
@@ -195,11 +448,36 @@ impl App {
       }
     };
     self.projects_list.state.select(Some(i));
+    self.projects_list.task_state.select(Some(0));
+    self.load_tasks();
+  }
+
+  /// Back to every project in progress, as the other screens expect
+  pub fn reset_projects_view(&mut self) {
+    if self.projects_list.tag_filter.take().is_some() {
+      self.projects_list.show_finished = false;
+      self.projects_list.state.select(Some(0));
+      self.get_projects();
+    } else {
+      self.show_projects(false);
+    }
+  }
+
+  /// Switches between the projects in progress and the finished ones
+  pub fn show_projects(&mut self, finished: bool) {
+    if self.projects_list.show_finished == finished {
+      return;
+    }
+    self.projects_list.show_finished = finished;
+    self.projects_list.state.select(Some(0));
+    self.get_projects();
   }
 
   pub fn list_projects(&mut self) {
     // TODO: refresh projects ?
     self.projects_list.state.select(Some(0));
+    self.projects_list.tasks_focused = false;
+    self.load_tasks();
     self.state = State::ProjectsList;
   }
 }
@@ -0,0 +1,155 @@
+use crate::structs::{
+  App, DayTotal, ProjectFilter, ReportPeriod, SessionFilter, State,
+};
+use crate::utils;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use time::{Date, Duration, OffsetDateTime};
+
+impl App {
+  pub fn handle_report_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Char('r') | KeyCode::Esc => {
+        self.state = State::None;
+      }
+      KeyCode::Char('h') | KeyCode::Left => self.prev_period(),
+      KeyCode::Char('l') | KeyCode::Right => self.next_period(),
+      KeyCode::Tab => self.switch_period(),
+      KeyCode::Char('#') => self.next_report_tag(),
+      KeyCode::Char('e') => {
+        self.load_estimates();
+        self.state = State::EstimatesReport;
+      }
+      _ => {}
+    }
+  }
+
+  pub fn handle_estimates_report_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Char('r') | KeyCode::Esc => {
+        self.state = State::None;
+      }
+      KeyCode::Char('e') => self.state = State::Report,
+      KeyCode::Char('#') => self.next_report_tag(),
+      _ => {}
+    }
+  }
+
+  /// The tag applies to both the period and the estimates
+  fn next_report_tag(&mut self) {
+    self.report.tag_filter =
+      self.get_next_tag(self.report.tag_filter.as_deref());
+    if let Some(date) = self.report.date {
+      self.set_report_date(date);
+    }
+    self.load_estimates();
+  }
+
+  fn load_estimates(&mut self) {
+    let tag = self.report.tag_filter.as_deref();
+    match self.repo.get_estimated_projects(tag) {
+      Ok(projects) => self.report.estimates = projects,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        self.report.estimates = vec![];
+      }
+    }
+  }
+
+  pub fn display_report(&mut self) {
+    let date = self
+      .report
+      .date
+      .unwrap_or(OffsetDateTime::now_local().unwrap().date());
+    self.set_report_date(date);
+    self.state = State::Report;
+  }
+
+  fn prev_period(&mut self) {
+    let Some(date) = self.report.date else {
+      return;
+    };
+    let (first_day, _) = get_period_bounds(date, self.report.period);
+    if let Some(date) = first_day.previous_day() {
+      self.set_report_date(date);
+    }
+  }
+
+  fn next_period(&mut self) {
+    let Some(date) = self.report.date else {
+      return;
+    };
+    let (_, last_day) = get_period_bounds(date, self.report.period);
+    if let Some(date) = last_day.next_day() {
+      self.set_report_date(date);
+    }
+  }
+
+  fn switch_period(&mut self) {
+    self.report.period = match self.report.period {
+      ReportPeriod::Week => ReportPeriod::Month,
+      ReportPeriod::Month => ReportPeriod::Week,
+    };
+    if let Some(date) = self.report.date {
+      self.set_report_date(date);
+    }
+  }
+
+  fn set_report_date(&mut self, date: Date) {
+    let (from, to) = get_period_bounds(date, self.report.period);
+    let filter = SessionFilter {
+      from: Some(from),
+      to: Some(to),
+      project: ProjectFilter::All,
+      tag: self.report.tag_filter.clone(),
+    };
+    let zone = utils::LocalZone;
+    self.report.date = Some(date);
+
+    match self.repo.get_project_totals(&filter, zone) {
+      Ok(projects) => self.report.projects = projects,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        self.report.projects = vec![];
+      }
+    }
+
+    let days = match self.repo.get_day_totals(&filter, zone) {
+      Ok(days) => days,
+      Err(err) => {
+        utils::notify(&err.to_string());
+        vec![]
+      }
+    };
+    self.report.days = fill_days(from, to, days);
+  }
+}
+
+pub fn get_period_bounds(date: Date, period: ReportPeriod) -> (Date, Date) {
+  match period {
+    ReportPeriod::Week => utils::get_week_bounds(date),
+    ReportPeriod::Month => utils::get_month_bounds(date),
+  }
+}
+
+/// Every day from the first to the last one, with no time when missing from
+/// the totals
+fn fill_days(from: Date, to: Date, totals: Vec<DayTotal>) -> Vec<DayTotal> {
+  let mut totals = totals.into_iter().peekable();
+  let mut days = vec![];
+  let mut date = from;
+
+  while date <= to {
+    match totals.next_if(|total| total.date == date) {
+      Some(total) => days.push(total),
+      None => days.push(DayTotal {
+        date,
+        duration: 0,
+        sessions: 0,
+      }),
+    }
+    date += Duration::DAY;
+  }
+  days
+}
@@ -0,0 +1,234 @@
+use crate::structs::{
+  App, CalendarRow, CalendarSection, SessionDraft, SessionRecord, State,
+};
+use crate::utils::{self, TimeZone};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use time::Time;
+use time::macros::format_description;
+
+impl App {
+  pub fn handle_session_project_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Down | KeyCode::Char('j') => self.next_project(),
+      KeyCode::Up | KeyCode::Char('k') => self.prev_project(),
+      KeyCode::Enter => {
+        let project_id = self.get_highlighted_project().map(|p| p.id);
+        self.choose_session_project(project_id);
+      }
+      KeyCode::Char('n') => self.choose_session_project(None),
+      KeyCode::Esc => self.cancel_session_edit(),
+      _ => {}
+    }
+  }
+
+  pub fn handle_session_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char(char) if char.is_ascii_digit() || char == ':' => {
+        self.input.push(char)
+      }
+      KeyCode::Backspace => {
+        self.input.pop();
+      }
+      KeyCode::Enter => match self.state {
+        State::SessionStartInput => self.set_session_start(),
+        State::SessionDurationInput => self.set_session_duration(),
+        _ => {}
+      },
+      KeyCode::Esc => self.cancel_session_edit(),
+      _ => {}
+    }
+  }
+
+  pub fn handle_delete_session_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Char('y') => {
+        if let Some(id) = self.get_highlighted_session().map(|s| s.id) {
+          if let Err(err) = self.repo.delete_session(id) {
+            utils::notify(&err.to_string());
+          }
+          self.refresh_after_session_edit();
+        }
+        self.state = State::Calendar;
+      }
+      KeyCode::Char('n') | KeyCode::Esc => self.state = State::Calendar,
+      _ => {}
+    }
+  }
+
+  /// Adds a work session on the selected date: project, start then duration
+  pub fn start_adding_session(&mut self) {
+    if self.calendar.selected_date.is_none() {
+      return;
+    }
+    self.calendar.draft = Some(SessionDraft::default());
+    self.reset_projects_view();
+    self.state = State::SessionProjectInput;
+  }
+
+  pub fn start_editing_duration(&mut self) {
+    let Some(session) = self.get_highlighted_session() else {
+      return;
+    };
+    let draft = SessionDraft {
+      id: Some(session.id),
+      project_id: session.project_id,
+      start: None,
+    };
+    self.input = (session.duration / 60).to_string();
+    self.calendar.draft = Some(draft);
+    self.state = State::SessionDurationInput;
+  }
+
+  pub fn start_moving_session(&mut self) {
+    let Some(session) = self.get_highlighted_session() else {
+      return;
+    };
+    let draft = SessionDraft {
+      id: Some(session.id),
+      project_id: session.project_id,
+      start: None,
+    };
+    self.reset_projects_view();
+    let index = self
+      .projects_list
+      .projects
+      .iter()
+      .position(|project| Some(project.id) == draft.project_id);
+    self.calendar.draft = Some(draft);
+    self.projects_list.state.select(Some(index.unwrap_or(0)));
+    self.state = State::SessionProjectInput;
+  }
+
+  /// The session selected among the ones of the expanded entry
+  pub fn get_highlighted_session(&self) -> Option<&SessionRecord> {
+    if let CalendarSection::Calendar = self.calendar.selected_section {
+      return None;
+    }
+    match self.calendar.get_selected_row()? {
+      CalendarRow::Session(index) => self.calendar.expanded_sessions.get(index),
+      CalendarRow::Project(_) => None,
+    }
+  }
+
+  fn choose_session_project(&mut self, project_id: Option<usize>) {
+    let Some(draft) = self.calendar.draft.as_mut() else {
+      return;
+    };
+    draft.project_id = project_id;
+
+    // Moving a session keeps its duration
+    if let Some(id) = draft.id {
+      let duration = self
+        .get_highlighted_session()
+        .filter(|session| session.id == id)
+        .map(|session| session.duration);
+      if let Some(duration) = duration {
+        self.save_session_draft(duration);
+      } else {
+        self.cancel_session_edit();
+      }
+      return;
+    }
+
+    self.input.clear();
+    self.state = State::SessionStartInput;
+  }
+
+  fn set_session_start(&mut self) {
+    let formats = [
+      format_description!("[hour]:[minute]"),
+      format_description!("[hour padding:none]:[minute]"),
+    ];
+    let start = formats
+      .iter()
+      .find_map(|format| Time::parse(self.input.trim(), format).ok());
+    let Some(start) = start else {
+      utils::notify("Invalid start time, expected HH:MM");
+      return;
+    };
+    let Some(draft) = self.calendar.draft.as_mut() else {
+      return;
+    };
+    draft.start = Some(start);
+    let project_id = draft.project_id;
+    // Defaults to the work duration of the session's project
+    let work_duration = self
+      .projects_list
+      .projects
+      .iter()
+      .find(|project| Some(project.id) == project_id)
+      .and_then(|project| project.durations.work);
+    self.input = work_duration
+      .unwrap_or(self.options.data.work_duration)
+      .to_string();
+    self.state = State::SessionDurationInput;
+  }
+
+  fn set_session_duration(&mut self) {
+    match self.input.trim().parse::<u32>() {
+      Ok(minutes) if minutes > 0 => self.save_session_draft(minutes * 60),
+      _ => utils::notify("Invalid duration"),
+    }
+  }
+
+  fn save_session_draft(&mut self, duration: u32) {
+    let Some(draft) = self.calendar.draft.take() else {
+      return;
+    };
+    let result = match (draft.id, draft.start, self.calendar.selected_date) {
+      (Some(id), _, _) => {
+        self.repo.update_session(id, draft.project_id, duration)
+      }
+      (None, Some(start), Some(date)) => {
+        let start = utils::LocalZone.assume(date.with_time(start));
+        self
+          .repo
+          .add_past_session(draft.project_id, start, duration)
+      }
+      _ => Ok(()),
+    };
+    if let Err(err) = result {
+      utils::notify(&err.to_string());
+    }
+    self.refresh_after_session_edit();
+    self.input.clear();
+    self.state = State::Calendar;
+  }
+
+  fn cancel_session_edit(&mut self) {
+    self.calendar.draft = None;
+    self.input.clear();
+    self.state = State::Calendar;
+  }
+
+  /// Reloads the day and the projects totals, keeping the entry that was
+  /// expanded open
+  fn refresh_after_session_edit(&mut self) {
+    let expanded_project = self
+      .calendar
+      .expanded
+      .and_then(|entry| self.calendar.sessions.get(entry))
+      .map(|entry| entry.project_id);
+    let selected = self.calendar.list_state.selected();
+
+    self.set_date_and_sessions(self.calendar.selected_date);
+    let entry = expanded_project.and_then(|project_id| {
+      self
+        .calendar
+        .sessions
+        .iter()
+        .position(|entry| entry.project_id == project_id)
+    });
+    if let Some(entry) = entry {
+      self.expand_entry(entry);
+    }
+    let last = self.calendar.get_rows_count().saturating_sub(1);
+    self
+      .calendar
+      .list_state
+      .select(selected.map(|row| row.min(last)));
+    self.get_projects();
+  }
+}
@@ -1,10 +1,25 @@
+use crate::daemon::{Request, Response};
+use crate::repository::Repository;
 use crate::structs::{App, Session, SessionType, State};
-use crate::utils;
+use crate::utils::{self, LocalZone, TimeZone};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use std::time::SystemTime;
+use time::OffsetDateTime;
 
 impl App {
+  pub fn handle_recover_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Char('r') => self.resume_recovered_session(),
+      KeyCode::Char('s') => self.save_recovered_session(),
+      KeyCode::Char('d') => self.discard_recovered_session(),
+      _ => {}
+    }
+  }
+
   pub fn start_work_input(&mut self) {
     if self.options.data.ask_before_work {
-      self.input = String::from(self.options.data.work_duration.to_string());
+      self.input = self.get_work_duration().to_string();
       self.state = State::WorkInput
     } else {
       self.start_work_session()
@@ -13,7 +28,8 @@ impl App {
 
   pub fn start_break_input(&mut self) {
     if self.options.data.ask_before_break {
-      self.input = String::from(self.options.data.break_duration.to_string());
+      let session_type = self.get_break_type();
+      self.input = self.get_break_duration(session_type).to_string();
       self.state = State::BreakInput;
     } else {
       self.start_break_session();
@@ -21,45 +37,206 @@ impl App {
   }
 
   pub fn start_work_session(&mut self) {
-    let time: u32 = self
-      .input
-      .parse()
-      .unwrap_or(self.options.data.work_duration);
-    self.current_session = Some(Session::new(SessionType::Work, time));
-    self.state = State::WorkSession;
+    let time: u32 = self.input.parse().unwrap_or(self.get_work_duration());
+    self.input.clear();
+    let project_id = self.projects_list.selected_id;
+    let mut session = Session::new(SessionType::Work, time, project_id);
+    session.task_id = self.get_selected_task_id();
+    self.state = if self.set_current_session(session) {
+      State::WorkSession
+    } else {
+      State::None
+    };
   }
 
   pub fn start_break_session(&mut self) {
+    let session_type = self.get_break_type();
     let time: u32 = self
       .input
       .parse()
-      .unwrap_or(self.options.data.break_duration);
-    self.current_session = Some(Session::new(SessionType::Break, time));
-    self.state = State::BreakSession;
+      .unwrap_or(self.get_break_duration(session_type));
+    self.input.clear();
+    let project_id = self.projects_list.selected_id;
+    let session = Session::new(session_type, time, project_id);
+    self.state = if self.set_current_session(session) {
+      State::BreakSession
+    } else {
+      State::None
+    };
   }
 
   pub fn stop_work_session(&mut self) {
-    let session = self.current_session.as_ref().unwrap();
-    let spent_time = utils::get_spent_time(session.start, session.duration);
-
-    if let Some(project_id) = self.get_selected_project().map(|p| p.id.clone())
-    {
-      let updated = self.repo.add_session(project_id, spent_time);
-      if updated.is_err() {
-        utils::notify("Error when updating project spent time");
-      }
-    }
-
+    self.save_session();
     utils::notify("Break Time?");
-    self.current_session = None;
+    self.clear_current_session();
   }
 
   pub fn stop_break_session(&mut self) {
+    self.save_session();
     utils::notify("Back to work?");
     self.state = State::ConfirmWork;
+    self.clear_current_session();
+  }
+
+  fn save_session(&mut self) {
+    if let Some(client) = self.daemon.as_mut() {
+      match client.send(&Request::Stop) {
+        // Already saved when the daemon ended it first
+        Ok(_) => return,
+        // The daemon is gone, the session is saved by the TUI instead
+        Err(_) => self.daemon = None,
+      }
+    }
+
+    let session = self.current_session.as_mut().unwrap();
+    if save_session(&mut self.repo, session).is_err() {
+      utils::notify("Error when saving the session");
+    }
+  }
+
+  /// False when the daemon refused the session, e.g. when another client
+  /// already started one
+  fn set_current_session(&mut self, session: Session) -> bool {
+    if let Some(client) = self.daemon.as_mut() {
+      let request = Request::Start {
+        session_type: session.session_type,
+        duration: session.duration,
+        project_id: session.project_id,
+        task_id: session.task_id,
+      };
+      match client.send(&request) {
+        Ok(Response {
+          error: Some(err), ..
+        }) => {
+          utils::notify(&err);
+          return false;
+        }
+        Ok(response) => {
+          self.current_session = response.session;
+          return self.current_session.is_some();
+        }
+        // The daemon is gone, the session is kept by the TUI from now on
+        Err(_) => self.daemon = None,
+      }
+    }
+
+    if self.repo.save_active_session(&session).is_err() {
+      utils::notify("Error when saving the session");
+    }
+    self.current_session = Some(session);
+    true
+  }
+
+  fn clear_current_session(&mut self) {
+    if self.daemon.is_none() && self.repo.clear_active_session().is_err() {
+      utils::notify("Error when clearing the session");
+    }
     self.current_session = None;
   }
 
+  /// Follows the session owned by the daemon, it can be started, paused or
+  /// stopped from other clients
+  pub fn sync_with_daemon(&mut self) {
+    let Some(client) = self.daemon.as_mut() else {
+      return;
+    };
+    let session = match client.request(&Request::Status) {
+      Ok(response) => response.session,
+      Err(_) => {
+        // The daemon is gone, the session is kept by the TUI from now on
+        self.daemon = None;
+        if let Some(session) = self.current_session.as_ref() {
+          let _ = self.repo.save_active_session(session);
+        }
+        return;
+      }
+    };
+
+    match (self.current_session.take(), session) {
+      (None, Some(session)) => {
+        self.state = get_session_state(Some(&session));
+        self.current_session = Some(session);
+      }
+      (Some(_), Some(session)) => self.current_session = Some(session),
+      (Some(ended), None) => match ended.session_type {
+        SessionType::Work => self.state = State::ConfirmBreak,
+        _ => self.state = State::ConfirmWork,
+      },
+      (None, None) => {}
+    }
+  }
+
+  /// Continues a session left running when tomato was closed
+  pub fn resume_recovered_session(&mut self) {
+    self.state = get_session_state(self.current_session.as_ref());
+  }
+
+  /// Keeps the time spent in a session left running when tomato was closed
+  pub fn save_recovered_session(&mut self) {
+    self.save_session();
+    self.discard_recovered_session();
+  }
+
+  pub fn discard_recovered_session(&mut self) {
+    self.clear_current_session();
+    self.state = State::None;
+  }
+
+  fn get_break_type(&self) -> SessionType {
+    let done = get_cycle_count(&self.repo).unwrap_or_default();
+    get_break_type(done, self.options.data.sessions_before_long_break)
+  }
+
+  /// The selected project's durations come before the options
+  fn get_work_duration(&self) -> u32 {
+    let durations = self.get_selected_project().map(|p| p.durations);
+    durations
+      .and_then(|durations| durations.work)
+      .unwrap_or(self.options.data.work_duration)
+  }
+
+  fn get_break_duration(&self, session_type: SessionType) -> u32 {
+    let durations = self
+      .get_selected_project()
+      .map(|p| p.durations)
+      .unwrap_or_default();
+    match session_type {
+      SessionType::LongBreak => durations
+        .long_break
+        .unwrap_or(self.options.data.long_break_duration),
+      _ => durations
+        .short_break
+        .unwrap_or(self.options.data.break_duration),
+    }
+  }
+
+  /// Position in the current cycle of work sessions, e.g. "3/4"
+  pub fn get_cycle_position(&self) -> Option<String> {
+    let session = self.current_session.as_ref()?;
+    get_cycle_position(
+      get_cycle_count(&self.repo).unwrap_or_default(),
+      self.options.data.sessions_before_long_break,
+      session.session_type,
+    )
+  }
+
+  pub fn toggle_pause(&mut self) {
+    if let Some(client) = self.daemon.as_mut() {
+      match client.request(&Request::Pause) {
+        Ok(response) => self.current_session = response.session,
+        Err(err) => utils::notify(&err.to_string()),
+      }
+      return;
+    }
+
+    if let Some(session) = self.current_session.as_mut() {
+      session.toggle_pause();
+      if self.repo.save_active_session(session).is_err() {
+        utils::notify("Error when saving the session");
+      }
+    }
+  }
+
   pub fn toggle_session(&mut self) {
     match self.state {
       State::ConfirmBreak => {
@@ -76,3 +253,73 @@ impl App {
     }
   }
 }
+
+/// Records a session that just ended, returns the time spent in it
+pub fn save_session(
+  repo: &mut Repository,
+  session: &mut Session,
+) -> Result<u32, rusqlite::Error> {
+  let spent_time = utils::get_spent_time(
+    session.start,
+    session.duration,
+    session.get_paused_time(),
+  );
+  let completed = spent_time >= session.duration * 60;
+  session.end = Some(SystemTime::now());
+
+  repo.add_session(session, spent_time, completed)?;
+  Ok(spent_time)
+}
+
+pub fn get_session_state(session: Option<&Session>) -> State {
+  match session.map(|session| session.session_type) {
+    None => State::None,
+    Some(SessionType::Work) => State::WorkSession,
+    Some(_) => State::BreakSession,
+  }
+}
+
+/// Work sessions done today since the last long break, saved ones only so
+/// the TUI and the commands agree
+pub fn get_cycle_count(repo: &Repository) -> Result<u32, rusqlite::Error> {
+  let today = LocalZone.to_local(OffsetDateTime::now_utc()).date();
+  repo.get_cycle_count(&today, LocalZone)
+}
+
+/// A long break is due once the cycle is done, it stays due until one is
+/// taken
+pub fn get_break_type(done: u32, sessions: u32) -> SessionType {
+  if sessions > 0 && done >= sessions {
+    SessionType::LongBreak
+  } else {
+    SessionType::Break
+  }
+}
+
+/// Position in a cycle of work sessions, `done` being the work sessions
+/// already done since the last long break. It stays on the last one while a
+/// skipped long break is still due
+pub fn get_cycle_position(
+  done: u32,
+  sessions: u32,
+  session_type: SessionType,
+) -> Option<String> {
+  if sessions == 0 {
+    return None;
+  }
+  let position = match session_type {
+    SessionType::Work => (done + 1).min(sessions),
+    _ => done.min(sessions),
+  };
+
+  Some(format!("{}/{}", position, sessions))
+}
+
+/// Time left in a session, None once it's over
+pub fn get_time_left(session: &Session) -> Option<u32> {
+  utils::render_timer(
+    session.start,
+    session.duration,
+    session.get_paused_time(),
+  )
+}
@@ -0,0 +1,191 @@
+use crate::structs::{App, State, Task};
+use crate::utils;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+impl App {
+  pub fn handle_tasks_input(&mut self, key_event: KeyEvent) {
+    match key_event.code {
+      KeyCode::Char('q') => self.exit(),
+      KeyCode::Down | KeyCode::Char('j') => self.next_task(),
+      KeyCode::Up | KeyCode::Char('k') => self.prev_task(),
+      KeyCode::Left | KeyCode::Char('h') | KeyCode::Esc => {
+        self.projects_list.tasks_focused = false;
+      }
+      KeyCode::Char('a') => self.state = State::TaskInputAdd,
+      KeyCode::Char('u') => {
+        if let Some(task) = self.get_highlighted_task() {
+          self.input = task.name.clone();
+          self.state = State::TaskInputUpdate;
+        }
+      }
+      KeyCode::Char('e') => {
+        if let Some(task) = self.get_highlighted_task() {
+          self.input = task.estimate.map(|e| e.to_string()).unwrap_or_default();
+          self.state = State::TaskInputEstimate;
+        }
+      }
+      KeyCode::Char('x') => self.toggle_task_done(),
+      KeyCode::Char(' ') => self.toggle_selected_task(),
+      _ => {}
+    }
+  }
+
+  /// Loads the tasks of the highlighted project
+  pub fn load_tasks(&mut self) {
+    let Some(project_id) = self.get_highlighted_project().map(|p| p.id) else {
+      self.projects_list.tasks = vec![];
+      self.projects_list.tasks_focused = false;
+      return;
+    };
+    match self.repo.get_tasks(project_id) {
+      Ok(tasks) => {
+        let last = tasks.len().saturating_sub(1);
+        let index = self.projects_list.task_state.selected().unwrap_or(0);
+        self.projects_list.task_state.select(Some(index.min(last)));
+        if let Some(selected) = &self.projects_list.selected_task {
+          let id = selected.id;
+          if let Some(task) = tasks.iter().find(|task| task.id == id) {
+            self.projects_list.selected_task = Some(task.clone());
+          }
+        }
+        self.projects_list.tasks = tasks;
+      }
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  pub fn focus_tasks(&mut self) {
+    if self.get_highlighted_project().is_some() {
+      self.projects_list.task_state.select(Some(0));
+      self.projects_list.tasks_focused = true;
+    }
+  }
+
+  pub fn add_task(&mut self) {
+    let name = self.input.trim();
+    let Some(project_id) = self.get_highlighted_project().map(|p| p.id) else {
+      return;
+    };
+    if name.is_empty() {
+      return;
+    }
+    match self.repo.add_task(project_id, name) {
+      Ok(()) => self.load_tasks(),
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  pub fn update_task(&mut self) {
+    let name = self.input.trim();
+    let Some(id) = self.get_highlighted_task().map(|task| task.id) else {
+      return;
+    };
+    if name.is_empty() {
+      return;
+    }
+    match self.repo.update_task(id, name) {
+      Ok(()) => self.load_tasks(),
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  pub fn update_task_estimate(&mut self) {
+    let Some(id) = self.get_highlighted_task().map(|task| task.id) else {
+      return;
+    };
+    let estimate = match utils::parse_estimate(&self.input) {
+      Ok(estimate) => estimate,
+      Err(err) => return utils::notify(err),
+    };
+    match self.repo.set_task_estimate(id, estimate) {
+      Ok(()) => self.load_tasks(),
+      Err(err) => utils::notify(&err.to_string()),
+    }
+  }
+
+  fn toggle_task_done(&mut self) {
+    let Some(task) = self.get_highlighted_task() else {
+      return;
+    };
+    let (id, done) = (task.id, !task.done);
+    if let Err(err) = self.repo.set_task_done(id, done) {
+      utils::notify(&err.to_string());
+      return;
+    }
+    if done && self.get_selected_task_id() == Some(id) {
+      self.projects_list.selected_task = None;
+    }
+    self.load_tasks();
+  }
+
+  /// Selecting a task selects its project too
+  fn toggle_selected_task(&mut self) {
+    let Some(task) = self.get_highlighted_task().cloned() else {
+      return;
+    };
+    if task.done {
+      utils::notify("This task is done");
+      return;
+    }
+    let selected = self.get_selected_task_id() != Some(task.id);
+    if let Err(err) = self.repo.set_selected_task(selected.then_some(task.id)) {
+      utils::notify(&err.to_string());
+      return;
+    }
+    if selected && self.projects_list.selected_id != Some(task.project_id) {
+      self.set_selected_project(task.project_id);
+    }
+    self.projects_list.selected_task = selected.then_some(task);
+  }
+
+  /// Drops the selected task when its project isn't the selected one anymore
+  pub fn sync_selected_task(&mut self) {
+    let Some(task) = &self.projects_list.selected_task else {
+      return;
+    };
+    if self.projects_list.selected_id == Some(task.project_id) {
+      return;
+    }
+    if let Err(err) = self.repo.set_selected_task(None) {
+      utils::notify(&err.to_string());
+    }
+    self.projects_list.selected_task = None;
+  }
+
+  pub fn get_selected_task_id(&self) -> Option<usize> {
+    self
+      .projects_list
+      .selected_task
+      .as_ref()
+      .map(|task| task.id)
+  }
+
+  pub fn get_highlighted_task(&self) -> Option<&Task> {
+    let index = self.projects_list.task_state.selected().unwrap_or(0);
+    self.projects_list.tasks.get(index)
+  }
+
+  fn next_task(&mut self) {
+    let count = self.projects_list.tasks.len();
+    if count == 0 {
+      return;
+    }
+    let index = match self.projects_list.task_state.selected() {
+      Some(index) if index + 1 < count => index + 1,
+      _ => 0,
+    };
+    self.projects_list.task_state.select(Some(index));
+  }
+
+  fn prev_task(&mut self) {
+    let count = self.projects_list.tasks.len();
+    if count == 0 {
+      return;
+    }
+    let index = match self.projects_list.task_state.selected() {
+      Some(index) if index > 0 => index - 1,
+      _ => count - 1,
+    };
+    self.projects_list.task_state.select(Some(index));
+  }
+}
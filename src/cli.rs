@@ -0,0 +1,718 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write, stdout};
+use std::thread;
+use std::time::Duration;
+use time::{Date, OffsetDateTime, macros::format_description};
+
+use crate::app::sessions::{
+  get_break_type, get_cycle_count, get_cycle_position, get_time_left,
+  save_session,
+};
+use crate::daemon::{self, Request, Response, TuiLock, client::DaemonClient};
+use crate::export::{self, ExportFormat};
+use crate::import::{self, DateFormat, ImportSource};
+use crate::repository::Repository;
+use crate::structs::{
+  Project, ProjectFilter, Session, SessionFilter, SessionType, UserConfig,
+  UserState,
+};
+use crate::utils::{
+  LocalZone, get_estimate_accuracy, parse_durations, render_durations,
+  render_estimate, render_timer_seconds,
+};
+
+/// Pomodoro timer, starts the TUI when no command is given
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+  /// Start a work session, or a break
+  Start {
+    /// Duration in minutes, defaults to the one set in the options
+    #[arg(short, long)]
+    minutes: Option<u32>,
+    /// Name of the project to work on, defaults to the selected one
+    #[arg(short, long)]
+    project: Option<String>,
+    /// Start a break instead of a work session
+    #[arg(short, long = "break")]
+    is_break: bool,
+  },
+  /// Stop the running session and save the time spent
+  Stop,
+  /// Pause the running session, or resume it
+  Pause,
+  /// Show the running session
+  Status {
+    #[arg(short, long, value_enum, default_value_t = StatusFormat::Plain)]
+    format: StatusFormat,
+    /// Keep printing the status every second
+    #[arg(long)]
+    follow: bool,
+  },
+  /// Manage projects
+  Projects {
+    #[command(subcommand)]
+    command: ProjectsCommand,
+  },
+  /// Show the time spent per project on a day
+  Report {
+    /// Day as YYYY-MM-DD, defaults to today
+    #[arg(short, long)]
+    date: Option<String>,
+    /// Only the projects with this tag
+    #[arg(short, long)]
+    tag: Option<String>,
+  },
+  /// Export sessions or projects as CSV or JSON
+  Export {
+    data: ExportData,
+    /// Defaults to the output file extension, CSV otherwise
+    #[arg(short, long, value_enum)]
+    format: Option<ExportFormat>,
+    #[command(flatten)]
+    filter: ExportFilter,
+    /// File to write, the standard output by default
+    #[arg(short, long)]
+    output: Option<String>,
+  },
+  /// Import sessions tracked with another tool
+  Import {
+    source: ImportSource,
+    /// Exported file, or Timewarrior's data folder
+    path: String,
+    /// How the CSV dates are written, detected from the file by default
+    #[arg(long)]
+    date_format: Option<DateFormat>,
+    /// Show what would be imported without writing anything
+    #[arg(long)]
+    dry_run: bool,
+  },
+  /// Run the daemon owning the running session, the TUI and commands use
+  /// it while it runs
+  Daemon,
+  /// Manage the database
+  Db {
+    #[command(subcommand)]
+    command: DbCommand,
+  },
+}
+
+#[derive(Args)]
+pub struct ExportFilter {
+  /// First day as YYYY-MM-DD
+  #[arg(long)]
+  from: Option<String>,
+  /// Last day as YYYY-MM-DD, included
+  #[arg(long)]
+  to: Option<String>,
+  /// Name of the project, finished or not
+  #[arg(short, long)]
+  project: Option<String>,
+  /// Only the projects with this tag
+  #[arg(short, long)]
+  tag: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StatusFormat {
+  Plain,
+  Json,
+  /// JSON for a waybar custom module
+  Waybar,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportData {
+  Sessions,
+  Projects,
+}
+
+#[derive(Subcommand)]
+pub enum ProjectsCommand {
+  /// List projects in progress
+  List {
+    /// List the finished projects instead
+    #[arg(long)]
+    finished: bool,
+  },
+  /// Add a project
+  Add { name: String },
+  /// Mark a project as finished
+  Finish { name: String },
+  /// Bring a finished project back in progress
+  Unfinish { name: String },
+  /// Set the pomodoros a project should take, removed when left out
+  Estimate {
+    name: String,
+    pomodoros: Option<u32>,
+  },
+  /// Set the work, break and long break minutes of a project as 50/10/20,
+  /// the options apply to the ones left empty
+  Durations {
+    name: String,
+    durations: Option<String>,
+  },
+  /// Compare the finished projects to their estimate
+  Estimates {
+    /// Only the projects with this tag
+    #[arg(short, long)]
+    tag: Option<String>,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum DbCommand {
+  /// Copy the database to a new location and use it from now on
+  Move { path: String },
+}
+
+pub fn run(
+  command: Command,
+  user_config: &UserConfig,
+) -> Result<(), Box<dyn Error>> {
+  let mut repo = Repository::new(user_config)?;
+  match command {
+    Command::Start {
+      minutes,
+      project,
+      is_break,
+    } => start(&repo, minutes, project, is_break),
+    Command::Stop => stop(&mut repo),
+    Command::Pause => pause(&repo),
+    Command::Status { format, follow } => {
+      status(&repo, format)?;
+      if !follow {
+        return Ok(());
+      }
+      loop {
+        thread::sleep(Duration::from_secs(1));
+        status(&repo, format)?;
+      }
+    }
+    Command::Projects { command } => match command {
+      ProjectsCommand::List { finished } => list_projects(&repo, finished),
+      ProjectsCommand::Add { name } => {
+        repo.add_project(name.trim())?;
+        println!("Project {} added", name.trim());
+        Ok(())
+      }
+      ProjectsCommand::Finish { name } => {
+        let project = find_project(&repo, &name)?;
+        repo.mark_project_finished(project.id)?;
+        println!("Project {} finished", project.name);
+        Ok(())
+      }
+      ProjectsCommand::Unfinish { name } => {
+        let project = repo
+          .get_finished_projects()?
+          .into_iter()
+          .find(|project| project.name.eq_ignore_ascii_case(name.trim()))
+          .ok_or_else(|| format!("No finished project named {}", name))?;
+        repo.mark_project_unfinished(project.id)?;
+        println!("Project {} back in progress", project.name);
+        Ok(())
+      }
+      ProjectsCommand::Estimate { name, pomodoros } => {
+        let project = find_project(&repo, &name)?;
+        let pomodoros = pomodoros.filter(|pomodoros| *pomodoros > 0);
+        repo.set_project_estimate(project.id, pomodoros)?;
+        match pomodoros {
+          Some(pomodoros) => {
+            println!("Project {} estimated at {}", project.name, pomodoros)
+          }
+          None => println!("Project {} has no estimate", project.name),
+        }
+        Ok(())
+      }
+      ProjectsCommand::Durations { name, durations } => {
+        let project = find_project(&repo, &name)?;
+        let durations = parse_durations(durations.as_deref().unwrap_or(""))?;
+        repo.set_project_durations(project.id, durations)?;
+        match render_durations(durations).as_str() {
+          "" => println!("Project {} uses the options", project.name),
+          durations => println!("Project {} runs {}", project.name, durations),
+        }
+        Ok(())
+      }
+      ProjectsCommand::Estimates { tag } => list_estimates(&repo, tag),
+    },
+    Command::Report { date, tag } => report(&repo, date, tag),
+    Command::Export {
+      data,
+      format,
+      filter,
+      output,
+    } => export(&repo, data, format, filter, output),
+    Command::Import {
+      source,
+      path,
+      date_format,
+      dry_run,
+    } => import(&mut repo, source, &path, date_format, dry_run),
+    Command::Daemon => daemon::run(user_config),
+    Command::Db { command } => match command {
+      DbCommand::Move { path } => move_db(user_config, &path),
+    },
+  }
+}
+
+fn start(
+  repo: &Repository,
+  minutes: Option<u32>,
+  project: Option<String>,
+  is_break: bool,
+) -> Result<(), Box<dyn Error>> {
+  if get_status(repo)?.session.is_some() {
+    return Err("A session is already running".into());
+  }
+
+  let options = repo.create_of_get_options()?;
+  let project = match project {
+    Some(name) => Some(find_project(repo, &name)?),
+    None => repo
+      .get_projects_in_progress()?
+      .into_iter()
+      .find(|project| project.selected),
+  };
+  // The project's durations come before the options
+  let durations = project.as_ref().map(|p| p.durations).unwrap_or_default();
+  let (session_type, default_duration) = if is_break {
+    let done = get_cycle_count(repo)?;
+    match get_break_type(done, options.sessions_before_long_break) {
+      SessionType::LongBreak => (
+        SessionType::LongBreak,
+        durations.long_break.unwrap_or(options.long_break_duration),
+      ),
+      _ => (
+        SessionType::Break,
+        durations.short_break.unwrap_or(options.break_duration),
+      ),
+    }
+  } else {
+    (
+      SessionType::Work,
+      durations.work.unwrap_or(options.work_duration),
+    )
+  };
+  let duration = minutes.unwrap_or(default_duration);
+  let project_id = project.as_ref().map(|p| p.id);
+  // The selected task goes with its project, for work only
+  let task_id = repo
+    .get_selected_task()?
+    .filter(|task| !is_break && Some(task.project_id) == project_id)
+    .map(|task| task.id);
+  if let Some(mut client) = DaemonClient::connect() {
+    client.request(&Request::Start {
+      session_type,
+      duration,
+      project_id,
+      task_id,
+    })?;
+  } else {
+    check_tui_lock()?;
+    let mut session = Session::new(session_type, duration, project_id);
+    session.task_id = task_id;
+    repo.save_active_session(&session)?;
+  }
+
+  match project {
+    Some(project) => println!("Started {}m on {}", duration, project.name),
+    None => println!("Started {}m", duration),
+  }
+  Ok(())
+}
+
+fn stop(repo: &mut Repository) -> Result<(), Box<dyn Error>> {
+  let spent_time = if let Some(mut client) = DaemonClient::connect() {
+    let response = client.request(&Request::Stop)?;
+    response.spent_time.unwrap_or_default()
+  } else {
+    check_tui_lock()?;
+    let Some(mut session) = repo.get_active_session()? else {
+      return Err("No session is running".into());
+    };
+    let spent_time = save_session(repo, &mut session)?;
+    repo.clear_active_session()?;
+    spent_time
+  };
+
+  println!("Stopped after {}", render_timer_seconds(spent_time));
+  Ok(())
+}
+
+fn pause(repo: &Repository) -> Result<(), Box<dyn Error>> {
+  let paused = if let Some(mut client) = DaemonClient::connect() {
+    let response = client.request(&Request::Pause)?;
+    response.session.is_some_and(|session| session.is_paused())
+  } else {
+    check_tui_lock()?;
+    let Some(mut session) = repo.get_active_session()? else {
+      return Err("No session is running".into());
+    };
+    session.toggle_pause();
+    repo.save_active_session(&session)?;
+    session.is_paused()
+  };
+
+  if paused {
+    println!("Paused");
+  } else {
+    println!("Resumed");
+  }
+  Ok(())
+}
+
+/// Without the daemon an open TUI keeps the session in memory, it would
+/// overwrite the changes made here or save the session a second time
+fn check_tui_lock() -> Result<(), Box<dyn Error>> {
+  if TuiLock::is_held() {
+    return Err(
+      "tomato is open, use it or run `tomato daemon` to share the session"
+        .into(),
+    );
+  }
+  Ok(())
+}
+
+/// Status from the daemon when it runs, from the database otherwise
+fn get_status(repo: &Repository) -> Result<Response, Box<dyn Error>> {
+  if let Some(mut client) = DaemonClient::connect() {
+    return client.request(&Request::Status);
+  }
+
+  let session = repo.get_active_session()?;
+  let project_name = match session.as_ref().and_then(|s| s.project_id) {
+    Some(id) => repo.get_project_by_id(id as i32)?.map(|p| p.name),
+    None => None,
+  };
+  Ok(Response {
+    time_left: session.as_ref().and_then(get_time_left),
+    session,
+    project_name,
+    ..Response::default()
+  })
+}
+
+#[derive(Serialize)]
+struct StatusOutput {
+  session_type: Option<SessionType>,
+  time_left: Option<u32>,
+  paused: bool,
+  project: Option<String>,
+  cycle: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WaybarOutput {
+  text: String,
+  tooltip: String,
+  class: &'static str,
+  percentage: u32,
+}
+
+fn status(
+  repo: &Repository,
+  format: StatusFormat,
+) -> Result<(), Box<dyn Error>> {
+  let status = get_status(repo)?;
+  let options = repo.create_of_get_options()?;
+  let session = status.session.as_ref();
+  let cycle = match session {
+    Some(session) => get_cycle_position(
+      get_cycle_count(repo)?,
+      options.sessions_before_long_break,
+      session.session_type,
+    ),
+    None => None,
+  };
+  let output = StatusOutput {
+    session_type: session.map(|session| session.session_type),
+    time_left: status.time_left,
+    paused: session.is_some_and(|session| session.is_paused()),
+    project: status.project_name,
+    cycle,
+  };
+
+  let line = match format {
+    StatusFormat::Plain => format_plain_status(&output),
+    StatusFormat::Json => serde_json::to_string(&output)?,
+    StatusFormat::Waybar => {
+      let duration = session.map_or(0, |session| session.duration * 60);
+      serde_json::to_string(&format_waybar_status(&output, duration))?
+    }
+  };
+  println!("{}", line);
+  stdout().flush()?;
+  Ok(())
+}
+
+fn get_session_label(session_type: SessionType) -> &'static str {
+  match session_type {
+    SessionType::Work => "Work session",
+    SessionType::Break => "Break",
+    SessionType::LongBreak => "Long break",
+  }
+}
+
+fn format_plain_status(output: &StatusOutput) -> String {
+  let Some(session_type) = output.session_type else {
+    return String::from("No session running");
+  };
+
+  let mut line = get_session_label(session_type).to_string();
+  if let Some(project) = &output.project {
+    line += &format!(": {}", project);
+  }
+  match output.time_left {
+    Some(seconds) => {
+      line += &format!(", {} left", render_timer_seconds(seconds))
+    }
+    None => line += ", time is up",
+  }
+  if output.paused {
+    line += " (paused)";
+  }
+  if let Some(cycle) = &output.cycle {
+    line += &format!(" [{}]", cycle);
+  }
+  line
+}
+
+fn format_waybar_status(output: &StatusOutput, duration: u32) -> WaybarOutput {
+  let Some(session_type) = output.session_type else {
+    return WaybarOutput {
+      text: String::from("🍅"),
+      tooltip: String::from("No session running"),
+      class: "idle",
+      percentage: 0,
+    };
+  };
+
+  let time_left = output.time_left.unwrap_or_default();
+  let class = match session_type {
+    _ if output.paused => "paused",
+    SessionType::Work => "work",
+    SessionType::Break | SessionType::LongBreak => "break",
+  };
+  let percentage = match duration {
+    0 => 100,
+    _ => (duration - time_left.min(duration)) * 100 / duration,
+  };
+
+  WaybarOutput {
+    text: format!("🍅 {}", render_timer_seconds(time_left)),
+    tooltip: format_plain_status(output),
+    class,
+    percentage,
+  }
+}
+
+fn list_projects(
+  repo: &Repository,
+  finished: bool,
+) -> Result<(), Box<dyn Error>> {
+  if finished {
+    for project in repo.get_finished_projects()? {
+      println!(
+        "{} - {} in {} sessions, finished {}",
+        project.name,
+        render_timer_seconds(project.time_spent as u32),
+        render_estimate(project.work_sessions as u32, project.estimate),
+        project.finish_date.as_deref().unwrap_or("?")
+      );
+    }
+    return Ok(());
+  }
+
+  for project in repo.get_projects_in_progress()? {
+    let selected = if project.selected { ">" } else { " " };
+    println!(
+      "{} {} - {} in {} sessions",
+      selected,
+      project.name,
+      render_timer_seconds(project.time_spent as u32),
+      render_estimate(project.work_sessions as u32, project.estimate)
+    );
+  }
+  Ok(())
+}
+
+fn list_estimates(
+  repo: &Repository,
+  tag: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+  let projects = repo.get_estimated_projects(tag.as_deref())?;
+  for project in &projects {
+    let over = if project.is_over_estimate() { " !" } else { "" };
+    println!(
+      "{} - {} sessions{}",
+      project.name,
+      render_estimate(project.work_sessions as u32, project.estimate),
+      over
+    );
+  }
+  let accuracy = get_estimate_accuracy(&projects);
+  println!(
+    "{}/{} within estimate - {}% done overall - {}% off on average",
+    accuracy.within_estimate,
+    accuracy.projects,
+    accuracy.done_percent,
+    accuracy.average_gap
+  );
+  Ok(())
+}
+
+fn report(
+  repo: &Repository,
+  date: Option<String>,
+  tag: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+  let date = match date {
+    Some(date) => parse_date(&date)?,
+    None => OffsetDateTime::now_local()?.date(),
+  };
+  let sessions = repo.get_sessions_per_day(&date, LocalZone, tag.as_deref())?;
+  let summary = repo.get_day_summary(&date, LocalZone, tag.as_deref())?;
+
+  match tag {
+    Some(tag) => println!("{} #{}", date, tag),
+    None => println!("{}", date),
+  }
+  for session in sessions {
+    println!(
+      "  {} - {}",
+      session.project_name,
+      render_timer_seconds(session.duration)
+    );
+  }
+  println!(
+    "Focus {} - Break {} - {}/{} work sessions completed",
+    render_timer_seconds(summary.focus_time),
+    render_timer_seconds(summary.break_time),
+    summary.completed_sessions,
+    summary.work_sessions
+  );
+  Ok(())
+}
+
+fn export(
+  repo: &Repository,
+  data: ExportData,
+  format: Option<ExportFormat>,
+  filter: ExportFilter,
+  output: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+  let zone = LocalZone;
+  let project = match filter.project {
+    Some(name) => ProjectFilter::Project(
+      repo
+        .get_projects(&SessionFilter::default(), zone)?
+        .into_iter()
+        .find(|project| project.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("No project named {}", name))?
+        .id,
+    ),
+    None => ProjectFilter::All,
+  };
+  let filter = SessionFilter {
+    from: filter.from.as_deref().map(parse_date).transpose()?,
+    to: filter.to.as_deref().map(parse_date).transpose()?,
+    project,
+    tag: filter.tag,
+  };
+  let format = match (format, output.as_deref()) {
+    (Some(format), _) => format,
+    (None, Some(path)) => ExportFormat::from_path(path),
+    (None, None) => ExportFormat::Csv,
+  };
+
+  let mut writer: BufWriter<Box<dyn Write>> = match &output {
+    Some(path) => BufWriter::new(Box::new(File::create(path)?)),
+    None => BufWriter::new(Box::new(stdout())),
+  };
+  match data {
+    ExportData::Sessions => {
+      export::export_sessions(repo, &filter, zone, format, &mut writer)?
+    }
+    ExportData::Projects => {
+      export::export_projects(repo, &filter, zone, format, &mut writer)?
+    }
+  }
+  writer.flush()?;
+
+  if let Some(path) = output {
+    println!("Exported to {}", path);
+  }
+  Ok(())
+}
+
+fn import(
+  repo: &mut Repository,
+  source: ImportSource,
+  path: &str,
+  date_format: Option<DateFormat>,
+  dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+  let sessions = import::read_sessions(source, path, date_format, LocalZone)?;
+  let summary = repo.import_sessions(&sessions, dry_run)?;
+
+  if dry_run {
+    for session in &sessions {
+      println!("{}", import::format_session(session));
+    }
+    for name in &summary.new_projects {
+      println!("New project: {}", name);
+    }
+  }
+  let verb = if dry_run { "Would import" } else { "Imported" };
+  println!(
+    "{} {} sessions ({}), {} already there, {} new projects",
+    verb,
+    summary.imported,
+    render_timer_seconds(summary.duration),
+    summary.skipped,
+    summary.new_projects.len()
+  );
+  Ok(())
+}
+
+fn move_db(user_config: &UserConfig, path: &str) -> Result<(), Box<dyn Error>> {
+  Repository::copy_database(&user_config.db_location, path)?;
+
+  let new_config = UserConfig {
+    db_location: path.to_string(),
+  };
+  confy::store("tomato", "config", &new_config)?;
+  let mut user_state: UserState = confy::load("tomato", "state")?;
+  user_state.last_db_location = Some(path.to_string());
+  confy::store("tomato", "state", &user_state)?;
+
+  println!("Database moved to {}", path);
+  println!("The old one was left at {}", user_config.db_location);
+  Ok(())
+}
+
+fn find_project(
+  repo: &Repository,
+  name: &str,
+) -> Result<Project, Box<dyn Error>> {
+  repo
+    .get_projects_in_progress()?
+    .into_iter()
+    .find(|project| project.name.eq_ignore_ascii_case(name.trim()))
+    .ok_or_else(|| format!("No project in progress named {}", name).into())
+}
+
+pub fn parse_date(date: &str) -> Result<Date, Box<dyn Error>> {
+  let format = format_description!("[year]-[month]-[day]");
+  Ok(Date::parse(date, &format)?)
+}
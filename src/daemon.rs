@@ -0,0 +1,328 @@
+pub mod client;
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{
+  DirBuilder, Permissions, read_to_string, remove_file, set_permissions, write,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::app::sessions::{get_time_left, save_session};
+use crate::repository::Repository;
+use crate::structs::{Session, SessionType, UserConfig};
+use crate::utils;
+
+/// Requests sent to the daemon, one JSON object per line
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+  Start {
+    session_type: SessionType,
+    duration: u32,
+    project_id: Option<usize>,
+    #[serde(default)]
+    task_id: Option<usize>,
+  },
+  Stop,
+  Pause,
+  Status,
+  /// Keeps the connection open, the status is sent every second
+  Subscribe,
+}
+
+/// Answer to every request, with the session running once it was handled
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Response {
+  pub session: Option<Session>,
+  pub project_name: Option<String>,
+  pub time_left: Option<u32>,
+  /// Time spent in the session a stop request ended
+  pub spent_time: Option<u32>,
+  pub error: Option<String>,
+}
+
+pub fn get_socket_path() -> PathBuf {
+  get_socket_dir().join("tomato.sock")
+}
+
+/// The runtime directory is the user's own, without one the socket goes to a
+/// folder of the user in the shared temporary directory
+fn get_socket_dir() -> PathBuf {
+  dirs::runtime_dir().unwrap_or_else(|| {
+    std::env::temp_dir().join(format!("tomato-{}", get_uid()))
+  })
+}
+
+fn get_uid() -> u32 {
+  // SAFETY: getuid has no preconditions and can't fail
+  unsafe { libc::getuid() }
+}
+
+/// Whether the path exists and belongs to the current user, links not
+/// followed
+pub fn is_owned_by_user(path: &Path) -> bool {
+  path
+    .symlink_metadata()
+    .is_ok_and(|metadata| metadata.uid() == get_uid())
+}
+
+/// Creates the folder of the socket only the user can enter, refusing one
+/// another user made first
+fn create_socket_dir(dir: &Path) -> Result<(), Box<dyn Error>> {
+  if !dir.exists() {
+    DirBuilder::new().mode(0o700).create(dir)?;
+  }
+  let metadata = dir.symlink_metadata()?;
+  if !metadata.is_dir() || metadata.uid() != get_uid() {
+    return Err(format!("{} belongs to another user", dir.display()).into());
+  }
+  set_permissions(dir, Permissions::from_mode(0o700))?;
+  Ok(())
+}
+
+/// Held by a TUI while it runs, without the daemon the session lives in its
+/// memory and commands must leave it alone
+pub struct TuiLock {
+  path: PathBuf,
+}
+
+impl TuiLock {
+  fn get_path() -> PathBuf {
+    get_socket_dir().join("tomato-tui.pid")
+  }
+
+  pub fn acquire() -> Result<Self, Box<dyn Error>> {
+    if dirs::runtime_dir().is_none() {
+      create_socket_dir(&get_socket_dir())?;
+    }
+    let path = Self::get_path();
+    write(&path, std::process::id().to_string())?;
+    Ok(Self { path })
+  }
+
+  /// Whether a TUI still running holds it, the ones left by a crash don't
+  pub fn is_held() -> bool {
+    let path = Self::get_path();
+    if !is_owned_by_user(&path) {
+      return false;
+    }
+    read_to_string(path)
+      .ok()
+      .and_then(|pid| pid.trim().parse().ok())
+      .is_some_and(is_running)
+  }
+}
+
+impl Drop for TuiLock {
+  fn drop(&mut self) {
+    let _ = remove_file(&self.path);
+  }
+}
+
+fn is_running(pid: i32) -> bool {
+  // SAFETY: the null signal only checks that the process exists
+  unsafe { libc::kill(pid, 0) == 0 }
+}
+
+struct Daemon {
+  repo: Repository,
+  session: Option<Session>,
+}
+
+impl Daemon {
+  fn handle(&mut self, request: Request) -> Response {
+    let result = match request {
+      Request::Start {
+        session_type,
+        duration,
+        project_id,
+        task_id,
+      } => {
+        let mut session = Session::new(session_type, duration, project_id);
+        session.task_id = task_id;
+        self.start(session).map(|_| None)
+      }
+      Request::Stop => self.stop().map(Some),
+      Request::Pause => self.pause().map(|_| None),
+      Request::Status | Request::Subscribe => Ok(None),
+    };
+
+    let mut response = self.get_status();
+    match result {
+      Ok(spent_time) => response.spent_time = spent_time,
+      Err(err) => response.error = Some(err),
+    }
+    response
+  }
+
+  fn start(&mut self, session: Session) -> Result<(), String> {
+    if self.session.is_some() {
+      return Err(String::from("A session is already running"));
+    }
+    self
+      .repo
+      .save_active_session(&session)
+      .map_err(|err| err.to_string())?;
+    self.session = Some(session);
+    Ok(())
+  }
+
+  fn stop(&mut self) -> Result<u32, String> {
+    let Some(mut session) = self.session.take() else {
+      return Err(String::from("No session is running"));
+    };
+    let spent_time =
+      save_session(&mut self.repo, &mut session).map_err(|e| e.to_string())?;
+    self
+      .repo
+      .clear_active_session()
+      .map_err(|e| e.to_string())?;
+    Ok(spent_time)
+  }
+
+  fn pause(&mut self) -> Result<(), String> {
+    let Some(session) = self.session.as_mut() else {
+      return Err(String::from("No session is running"));
+    };
+    session.toggle_pause();
+    self
+      .repo
+      .save_active_session(session)
+      .map_err(|err| err.to_string())
+  }
+
+  /// Ends the session once its time is up, nobody may be watching it
+  fn check_timer(&mut self) {
+    let Some(session) = self.session.as_ref() else {
+      return;
+    };
+    if get_time_left(session).is_some() {
+      return;
+    }
+
+    let message = match session.session_type {
+      SessionType::Work => "Break Time?",
+      _ => "Back to work?",
+    };
+    if self.stop().is_err() {
+      utils::notify("Error when saving the session");
+    }
+    utils::notify(message);
+  }
+
+  fn get_status(&self) -> Response {
+    let project_name = self
+      .session
+      .as_ref()
+      .and_then(|session| session.project_id)
+      .and_then(|id| self.repo.get_project_by_id(id as i32).ok().flatten())
+      .map(|project| project.name);
+
+    Response {
+      session: self.session.clone(),
+      project_name,
+      time_left: self.session.as_ref().and_then(get_time_left),
+      spent_time: None,
+      error: None,
+    }
+  }
+}
+
+/// Runs the daemon until it's killed, a session left running by the TUI or a
+/// previous daemon is picked up
+pub fn run(user_config: &UserConfig) -> Result<(), Box<dyn Error>> {
+  let socket_path = get_socket_path();
+  if dirs::runtime_dir().is_none() {
+    create_socket_dir(&get_socket_dir())?;
+  }
+  if socket_path.symlink_metadata().is_ok() {
+    if !is_owned_by_user(&socket_path) {
+      let path = socket_path.display();
+      return Err(format!("{} belongs to another user", path).into());
+    }
+    if UnixStream::connect(&socket_path).is_ok() {
+      return Err("The daemon is already running".into());
+    }
+    remove_file(&socket_path)?;
+  }
+
+  let repo = Repository::new(user_config)?;
+  let session = repo.get_active_session()?;
+  let daemon = Arc::new(Mutex::new(Daemon { repo, session }));
+  let listener = UnixListener::bind(&socket_path)?;
+  set_permissions(&socket_path, Permissions::from_mode(0o600))?;
+  println!("Listening on {}", socket_path.display());
+
+  let timer_daemon = Arc::clone(&daemon);
+  thread::spawn(move || {
+    loop {
+      timer_daemon.lock().unwrap().check_timer();
+      thread::sleep(Duration::from_millis(250));
+    }
+  });
+
+  for stream in listener.incoming() {
+    let Ok(stream) = stream else {
+      continue;
+    };
+    let client_daemon = Arc::clone(&daemon);
+    thread::spawn(move || handle_client(stream, client_daemon));
+  }
+
+  Ok(())
+}
+
+fn handle_client(stream: UnixStream, daemon: Arc<Mutex<Daemon>>) {
+  let Ok(mut writer) = stream.try_clone() else {
+    return;
+  };
+  let reader = BufReader::new(stream);
+
+  for line in reader.lines() {
+    let Ok(line) = line else {
+      return;
+    };
+    let (response, subscribe) = match serde_json::from_str::<Request>(&line) {
+      Ok(request) => {
+        let subscribe = matches!(request, Request::Subscribe);
+        (daemon.lock().unwrap().handle(request), subscribe)
+      }
+      Err(err) => (
+        Response {
+          error: Some(err.to_string()),
+          ..Response::default()
+        },
+        false,
+      ),
+    };
+
+    if write_response(&mut writer, &response).is_err() {
+      return;
+    }
+    if subscribe {
+      loop {
+        thread::sleep(Duration::from_secs(1));
+        let response = daemon.lock().unwrap().get_status();
+        if write_response(&mut writer, &response).is_err() {
+          return;
+        }
+      }
+    }
+  }
+}
+
+fn write_response(
+  writer: &mut UnixStream,
+  response: &Response,
+) -> Result<(), Box<dyn Error>> {
+  let mut line = serde_json::to_string(response)?;
+  line.push('\n');
+  writer.write_all(line.as_bytes())?;
+  Ok(())
+}
@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+use super::{Request, Response, get_socket_path, is_owned_by_user};
+
+pub struct DaemonClient {
+  writer: UnixStream,
+  reader: BufReader<UnixStream>,
+}
+
+impl DaemonClient {
+  /// None when no daemon is running, or when the socket isn't the user's
+  pub fn connect() -> Option<Self> {
+    let socket_path = get_socket_path();
+    if !is_owned_by_user(&socket_path) {
+      return None;
+    }
+    let stream = UnixStream::connect(socket_path).ok()?;
+    stream.set_read_timeout(Some(Duration::from_secs(2))).ok()?;
+    let writer = stream.try_clone().ok()?;
+
+    Some(Self {
+      writer,
+      reader: BufReader::new(stream),
+    })
+  }
+
+  /// Sends a request, errors reported by the daemon are returned as such
+  pub fn request(
+    &mut self,
+    request: &Request,
+  ) -> Result<Response, Box<dyn Error>> {
+    let response = self.send(request)?;
+
+    match response.error {
+      Some(err) => Err(err.into()),
+      None => Ok(response),
+    }
+  }
+
+  /// Sends a request, only failing when the daemon can't be reached
+  pub fn send(
+    &mut self,
+    request: &Request,
+  ) -> Result<Response, Box<dyn Error>> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    self.writer.write_all(line.as_bytes())?;
+    self.read_response()
+  }
+
+  /// Reads the next status pushed after a subscribe request
+  pub fn read_response(&mut self) -> Result<Response, Box<dyn Error>> {
+    let mut line = String::new();
+    if self.reader.read_line(&mut line)? == 0 {
+      return Err("The daemon closed the connection".into());
+    }
+    Ok(serde_json::from_str(&line)?)
+  }
+}
@@ -0,0 +1,511 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{OffsetDateTime, UtcOffset};
+
+use crate::repository::Repository;
+use crate::structs::{Project, SessionFilter, SessionRecord, SessionType};
+use crate::utils::TimeZone;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+  Csv,
+  Json,
+  /// iCalendar, work sessions only
+  Ics,
+}
+
+impl ExportFormat {
+  /// JSON for a .json file, iCalendar for a .ics one, CSV otherwise
+  pub fn from_path(path: &str) -> Self {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+      Some(ext) if ext.eq_ignore_ascii_case("json") => ExportFormat::Json,
+      Some(ext) if ext.eq_ignore_ascii_case("ics") => ExportFormat::Ics,
+      _ => ExportFormat::Csv,
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct SessionRow {
+  project: Option<String>,
+  task: Option<String>,
+  date: String,
+  start: String,
+  end: Option<String>,
+  /// In seconds, pauses left out
+  duration: u32,
+  session_type: &'static str,
+  completed: bool,
+}
+
+#[derive(Serialize)]
+struct ProjectRow<'a> {
+  name: &'a str,
+  /// In seconds
+  time_spent: i32,
+  work_sessions: i32,
+  estimate: Option<u32>,
+  finished: bool,
+  creation_date: &'a str,
+  modification_date: &'a str,
+  finish_date: Option<&'a str>,
+  tags: &'a [String],
+}
+
+pub fn export_sessions(
+  repo: &Repository,
+  filter: &SessionFilter,
+  zone: impl TimeZone,
+  format: ExportFormat,
+  writer: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+  let sessions = repo.get_sessions(filter, &zone)?;
+  let to_rows = || {
+    sessions
+      .iter()
+      .map(|session| to_session_row(session, &zone))
+      .collect::<Result<Vec<_>, _>>()
+  };
+
+  match format {
+    ExportFormat::Json => write_json(writer, &to_rows()?),
+    ExportFormat::Ics => {
+      let database_id = repo.get_database_id()?;
+      write_ics(writer, &sessions, &database_id, OffsetDateTime::now_utc())
+    }
+    ExportFormat::Csv => {
+      write_csv_line(
+        writer,
+        &[
+          "project",
+          "task",
+          "date",
+          "start",
+          "end",
+          "duration",
+          "session_type",
+          "completed",
+        ],
+      )?;
+      for row in to_rows()? {
+        write_csv_line(
+          writer,
+          &[
+            row.project.as_deref().unwrap_or_default(),
+            row.task.as_deref().unwrap_or_default(),
+            &row.date,
+            &row.start,
+            row.end.as_deref().unwrap_or_default(),
+            &row.duration.to_string(),
+            row.session_type,
+            &row.completed.to_string(),
+          ],
+        )?;
+      }
+      Ok(())
+    }
+  }
+}
+
+pub fn export_projects(
+  repo: &Repository,
+  filter: &SessionFilter,
+  zone: impl TimeZone,
+  format: ExportFormat,
+  writer: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+  let projects = repo.get_projects(filter, zone)?;
+  let rows: Vec<ProjectRow> = projects.iter().map(to_project_row).collect();
+
+  match format {
+    ExportFormat::Json => write_json(writer, &rows),
+    ExportFormat::Ics => Err("Projects can't be exported as iCalendar".into()),
+    ExportFormat::Csv => {
+      write_csv_line(
+        writer,
+        &[
+          "name",
+          "time_spent",
+          "work_sessions",
+          "estimate",
+          "finished",
+          "creation_date",
+          "modification_date",
+          "finish_date",
+          "tags",
+        ],
+      )?;
+      for row in rows {
+        write_csv_line(
+          writer,
+          &[
+            row.name,
+            &row.time_spent.to_string(),
+            &row.work_sessions.to_string(),
+            &row.estimate.map(|e| e.to_string()).unwrap_or_default(),
+            &row.finished.to_string(),
+            row.creation_date,
+            row.modification_date,
+            row.finish_date.unwrap_or_default(),
+            &row.tags.join(","),
+          ],
+        )?;
+      }
+      Ok(())
+    }
+  }
+}
+
+/// Writes the sessions to a file, the format is picked from its extension
+pub fn export_sessions_to_file(
+  repo: &Repository,
+  filter: &SessionFilter,
+  zone: impl TimeZone,
+  path: &str,
+) -> Result<(), Box<dyn Error>> {
+  let mut writer = BufWriter::new(File::create(path)?);
+  let format = ExportFormat::from_path(path);
+  export_sessions(repo, filter, zone, format, &mut writer)?;
+  writer.flush()?;
+  Ok(())
+}
+
+fn to_session_row(
+  session: &SessionRecord,
+  zone: impl TimeZone,
+) -> Result<SessionRow, time::error::Format> {
+  let start = zone.to_local(session.start_date);
+  let end = match session.end_date {
+    Some(end) => Some(format_date_time(zone.to_local(end))?),
+    None => None,
+  };
+
+  Ok(SessionRow {
+    project: session.project_name.clone(),
+    task: session.task_name.clone(),
+    date: start.date().to_string(),
+    start: format_date_time(start)?,
+    end,
+    duration: session.duration,
+    session_type: session.session_type.as_str(),
+    completed: session.completed,
+  })
+}
+
+fn to_project_row(project: &Project) -> ProjectRow<'_> {
+  ProjectRow {
+    name: &project.name,
+    time_spent: project.time_spent,
+    work_sessions: project.work_sessions,
+    estimate: project.estimate,
+    finished: project.finished,
+    creation_date: &project.creation_date,
+    modification_date: &project.modification_date,
+    finish_date: project.finish_date.as_deref(),
+    tags: &project.tags,
+  }
+}
+
+fn format_date_time(
+  date_time: OffsetDateTime,
+) -> Result<String, time::error::Format> {
+  date_time.replace_nanosecond(0).unwrap().format(&Rfc3339)
+}
+
+fn write_json(
+  writer: &mut impl Write,
+  rows: &impl Serialize,
+) -> Result<(), Box<dyn Error>> {
+  serde_json::to_writer_pretty(&mut *writer, rows)?;
+  writeln!(writer)?;
+  Ok(())
+}
+
+/// Writes work sessions as RFC 5545 events, their UID comes from the session
+/// and database ids so importing the file again updates the events, the
+/// edited ones having a higher sequence
+fn write_ics(
+  writer: &mut impl Write,
+  sessions: &[SessionRecord],
+  database_id: &str,
+  stamp: OffsetDateTime,
+) -> Result<(), Box<dyn Error>> {
+  let date_format =
+    format_description!("[year][month][day]T[hour][minute][second]Z");
+  let mut lines = vec![
+    String::from("BEGIN:VCALENDAR"),
+    String::from("VERSION:2.0"),
+    String::from("PRODID:-//tomato//tomato//EN"),
+    String::from("CALSCALE:GREGORIAN"),
+  ];
+  let stamp = stamp.to_offset(UtcOffset::UTC).format(&date_format)?;
+
+  for session in sessions {
+    let SessionType::Work = session.session_type else {
+      continue;
+    };
+    let start = session.start_date.to_offset(UtcOffset::UTC);
+    let end = match session.end_date {
+      Some(end) => end.to_offset(UtcOffset::UTC),
+      None => start + time::Duration::seconds(session.duration as i64),
+    };
+    let summary = session.project_name.as_deref().unwrap_or("Work session");
+    lines.extend([
+      String::from("BEGIN:VEVENT"),
+      format!("UID:session-{}-{}@tomato", session.id, database_id),
+      format!("DTSTAMP:{}", stamp),
+      format!("DTSTART:{}", start.format(&date_format)?),
+      format!("DTEND:{}", end.format(&date_format)?),
+      format!("SEQUENCE:{}", session.revision),
+      format!("SUMMARY:{}", escape_ics(summary)),
+      String::from("END:VEVENT"),
+    ]);
+  }
+  lines.push(String::from("END:VCALENDAR"));
+
+  for line in lines {
+    write!(writer, "{}\r\n", fold_ics_line(&line))?;
+  }
+  Ok(())
+}
+
+/// Escapes the characters with a meaning in iCalendar text values
+fn escape_ics(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace('\n', "\\n")
+}
+
+/// Splits lines longer than 75 bytes, each continuation starting with a
+/// space, without cutting a character in half
+fn fold_ics_line(line: &str) -> String {
+  let mut folded = String::new();
+  let mut length = 0;
+  for char in line.chars() {
+    if length + char.len_utf8() > 75 {
+      folded.push_str("\r\n ");
+      length = 1;
+    }
+    folded.push(char);
+    length += char.len_utf8();
+  }
+  folded
+}
+
+fn write_csv_line(
+  writer: &mut impl Write,
+  fields: &[&str],
+) -> Result<(), Box<dyn Error>> {
+  let line: Vec<String> =
+    fields.iter().map(|field| escape_csv(field)).collect();
+  writeln!(writer, "{}", line.join(","))?;
+  Ok(())
+}
+
+/// Quotes a field when it holds a separator, a quote or a line break
+fn escape_csv(field: &str) -> String {
+  if field.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", field.replace('"', "\"\""))
+  } else {
+    field.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::structs::{ProjectFilter, Session};
+  use time::macros::{date, datetime, offset};
+
+  #[test]
+  fn test_escape_csv() {
+    assert_eq!(escape_csv("tomato"), "tomato");
+    assert_eq!(escape_csv("a, b"), "\"a, b\"");
+    assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+  }
+
+  #[test]
+  fn test_export_sessions_csv_filtered() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Write, edit").unwrap();
+    repo.add_project("Other").unwrap();
+    repo.add_task(1, "Draft").unwrap();
+    let start = datetime!(2025-03-10 09:00 UTC);
+    let session = Session::finished(SessionType::Work, 25, start);
+    repo.add_finished_session(session.with_project(1).with_task(1));
+    repo.add_finished_session(
+      Session::finished(SessionType::Break, 25, start).with_project(1),
+    );
+    repo.add_finished_session(
+      Session::finished(SessionType::Work, 25, start).with_project(2),
+    );
+    repo.add_finished_session(
+      Session::finished(SessionType::Work, 25, datetime!(2025-03-12 09:00 UTC))
+        .with_project(1),
+    );
+
+    let filter = SessionFilter {
+      from: Some(date!(2025 - 03 - 10)),
+      to: Some(date!(2025 - 03 - 11)),
+      project: ProjectFilter::Project(1),
+      ..Default::default()
+    };
+    let mut output = Vec::new();
+    export_sessions(
+      &repo,
+      &filter,
+      offset!(+1),
+      ExportFormat::Csv,
+      &mut output,
+    )
+    .unwrap();
+
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      "project,task,date,start,end,duration,session_type,completed\n\
+       \"Write, edit\",Draft,2025-03-10,2025-03-10T10:00:00+01:00,\
+       2025-03-10T10:25:00+01:00,1500,work,true\n\
+       \"Write, edit\",,2025-03-10,2025-03-10T10:00:00+01:00,\
+       2025-03-10T10:25:00+01:00,1500,break,true\n"
+    );
+  }
+
+  #[test]
+  fn test_export_unassigned_sessions() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    let start = datetime!(2025-03-10 09:00 UTC);
+    repo.add_finished_session(
+      Session::finished(SessionType::Work, 25, start).with_project(1),
+    );
+    repo.add_finished_session(Session::finished(SessionType::Work, 25, start));
+
+    let filter = SessionFilter {
+      project: ProjectFilter::Unassigned,
+      ..Default::default()
+    };
+    let mut output = Vec::new();
+    export_sessions(
+      &repo,
+      &filter,
+      offset!(UTC),
+      ExportFormat::Json,
+      &mut output,
+    )
+    .unwrap();
+
+    let rows: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["project"], serde_json::Value::Null);
+  }
+
+  #[test]
+  fn test_export_projects_json_in_range() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Worked on").unwrap();
+    repo.add_project("Idle").unwrap();
+    let start = datetime!(2025-03-10 09:00 UTC);
+    repo.add_finished_session(
+      Session::finished(SessionType::Work, 25, start).with_project(1),
+    );
+
+    let filter = SessionFilter {
+      from: Some(date!(2025 - 03 - 10)),
+      to: Some(date!(2025 - 03 - 10)),
+      ..Default::default()
+    };
+    let mut output = Vec::new();
+    export_projects(
+      &repo,
+      &filter,
+      offset!(UTC),
+      ExportFormat::Json,
+      &mut output,
+    )
+    .unwrap();
+
+    let rows: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let rows = rows.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["name"], "Worked on");
+    assert_eq!(rows[0]["time_spent"], 1500);
+    assert_eq!(rows[0]["work_sessions"], 1);
+  }
+
+  #[test]
+  fn test_export_sessions_ics() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Write; edit").unwrap();
+    let start = datetime!(2025-03-10 10:00 +01:00);
+    repo.add_finished_session(
+      Session::finished(SessionType::Work, 25, start).with_project(1),
+    );
+    repo.add_finished_session(
+      Session::finished(SessionType::Break, 25, start).with_project(1),
+    );
+    repo.add_finished_session(Session::finished(SessionType::Work, 25, start));
+
+    let mut output = Vec::new();
+    export_sessions(
+      &repo,
+      &SessionFilter::default(),
+      offset!(+1),
+      ExportFormat::Ics,
+      &mut output,
+    )
+    .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let database_id = repo.get_database_id().unwrap();
+    assert_eq!(database_id.len(), 16);
+    assert_ne!(
+      database_id,
+      Repository::in_memory().get_database_id().unwrap()
+    );
+    assert!(output.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(output.ends_with("END:VCALENDAR\r\n"));
+    assert_eq!(output.matches("BEGIN:VEVENT").count(), 2);
+    assert!(output.contains(&format!(
+      "BEGIN:VEVENT\r\nUID:session-1-{}@tomato\r\nDTSTAMP:",
+      database_id
+    )));
+    assert!(output.contains(
+      "DTSTART:20250310T090000Z\r\nDTEND:20250310T092500Z\r\nSEQUENCE:0\r\n\
+       SUMMARY:Write\\; edit\r\nEND:VEVENT\r\n"
+    ));
+    assert!(!output.contains("DTSTAMP:20250310T092500Z"));
+    assert!(output.contains(&format!("UID:session-3-{}@tomato", database_id)));
+    assert!(output.contains("SUMMARY:Work session"));
+
+    repo.update_session(1, Some(1), 1200).unwrap();
+    let mut output = Vec::new();
+    export_sessions(
+      &repo,
+      &SessionFilter::default(),
+      offset!(+1),
+      ExportFormat::Ics,
+      &mut output,
+    )
+    .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("DTEND:20250310T092000Z\r\nSEQUENCE:1\r\n"));
+  }
+
+  #[test]
+  fn test_fold_ics_line() {
+    let line = format!("SUMMARY:{}", "é".repeat(40));
+    let folded = fold_ics_line(&line);
+
+    assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+    assert_eq!(folded.replace("\r\n ", ""), line);
+  }
+}
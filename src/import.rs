@@ -0,0 +1,404 @@
+use clap::ValueEnum;
+use std::error::Error;
+use std::fs::{read_dir, read_to_string};
+use std::path::Path;
+use time::format_description::BorrowedFormatItem;
+use time::macros::format_description;
+use time::{Date, PrimitiveDateTime, Time};
+
+use crate::structs::ImportedSession;
+use crate::utils::{LocalZone, TimeZone, render_timer_seconds};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ImportSource {
+  /// Detailed CSV report
+  Toggl,
+  /// Detailed CSV report
+  Clockify,
+  /// A data file, or the data folder holding them
+  Timewarrior,
+}
+
+/// How the dates of a CSV report are written
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum DateFormat {
+  /// 2025-03-25
+  Iso,
+  /// 03/25/2025
+  MonthFirst,
+  /// 25/03/2025 or 25.03.2025
+  DayFirst,
+}
+
+impl DateFormat {
+  fn parse(self, date: &str) -> Option<Date> {
+    let formats: &[&[BorrowedFormatItem]] = match self {
+      DateFormat::Iso => &[format_description!("[year]-[month]-[day]")],
+      DateFormat::MonthFirst => &[format_description!("[month]/[day]/[year]")],
+      DateFormat::DayFirst => &[
+        format_description!("[day]/[month]/[year]"),
+        format_description!("[day].[month].[year]"),
+      ],
+    };
+    formats
+      .iter()
+      .find_map(|format| Date::parse(date, format).ok())
+  }
+}
+
+const TIME_FORMATS: &[&[BorrowedFormatItem]] = &[
+  format_description!("[hour]:[minute]:[second]"),
+  format_description!("[hour]:[minute]"),
+  format_description!("[hour repr:12]:[minute]:[second] [period]"),
+  format_description!("[hour repr:12]:[minute] [period]"),
+];
+
+/// Reads the sessions of an export, the CSV ones hold local times
+pub fn read_sessions(
+  source: ImportSource,
+  path: &str,
+  date_format: Option<DateFormat>,
+  zone: impl TimeZone,
+) -> Result<Vec<ImportedSession>, Box<dyn Error>> {
+  match source {
+    ImportSource::Toggl | ImportSource::Clockify => {
+      parse_csv_report(&read_to_string(path)?, date_format, zone)
+    }
+    ImportSource::Timewarrior => {
+      let path = Path::new(path);
+      if !path.is_dir() {
+        return parse_timewarrior(&read_to_string(path)?);
+      }
+
+      let mut files = read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+      files.retain(|file| file.extension().is_some_and(|ext| ext == "data"));
+      files.sort();
+      let mut sessions = vec![];
+      for file in files {
+        sessions.extend(parse_timewarrior(&read_to_string(file)?)?);
+      }
+      Ok(sessions)
+    }
+  }
+}
+
+/// Toggl and Clockify detailed reports share their column names, only the
+/// date and time formats differ. The date format is detected from the whole
+/// file when not given
+fn parse_csv_report(
+  content: &str,
+  date_format: Option<DateFormat>,
+  zone: impl TimeZone,
+) -> Result<Vec<ImportedSession>, Box<dyn Error>> {
+  let mut rows = parse_csv(content.trim_start_matches('\u{feff}')).into_iter();
+  let header = rows.next().ok_or("The file is empty")?;
+  let column = |name: &str| {
+    header
+      .iter()
+      .position(|title| title.trim().eq_ignore_ascii_case(name))
+      .ok_or_else(|| format!("Missing column: {}", name))
+  };
+  let project = column("Project")?;
+  let start_date = column("Start date")?;
+  let start_time = column("Start time")?;
+  let end_date = column("End date")?;
+  let end_time = column("End time")?;
+
+  let rows = rows
+    .filter(|row| row.iter().any(|field| !field.is_empty()))
+    .collect::<Vec<_>>();
+  if rows.is_empty() {
+    return Err("No sessions to import".into());
+  }
+  let date_format = match date_format {
+    Some(date_format) => date_format,
+    None => detect_date_format(rows.iter().flat_map(|row| {
+      [start_date, end_date]
+        .into_iter()
+        .filter_map(|column| row.get(column).map(|field| field.trim()))
+    }))?,
+  };
+
+  rows
+    .iter()
+    .enumerate()
+    .map(|(index, row)| {
+      let field = |column: usize| {
+        row
+          .get(column)
+          .map(|field| field.trim())
+          .ok_or_else(|| format!("Row {} is too short", index + 2))
+      };
+      let date_time = |date: usize, time: usize| {
+        parse_date_time(field(date)?, field(time)?, date_format)
+          .map_err(|err| format!("Row {}: {}", index + 2, err))
+      };
+      let name = field(project)?;
+      Ok(ImportedSession {
+        project: (!name.is_empty()).then(|| name.to_string()),
+        start: zone.assume(date_time(start_date, start_time)?),
+        end: zone.assume(date_time(end_date, end_time)?),
+      })
+    })
+    .collect()
+}
+
+/// Picks the only format reading every date, 03/04/2025 alone could be
+/// month or day first so it takes a date like 03/25/2025 to tell
+fn detect_date_format<'a>(
+  dates: impl Iterator<Item = &'a str> + Clone,
+) -> Result<DateFormat, Box<dyn Error>> {
+  let formats = DateFormat::value_variants()
+    .iter()
+    .copied()
+    .filter(|format| dates.clone().all(|date| format.parse(date).is_some()))
+    .collect::<Vec<_>>();
+
+  match formats[..] {
+    [format] => Ok(format),
+    [] => Err("Unknown date format, pass it with --date-format".into()),
+    _ => Err(
+      "Dates could be month or day first, pass --date-format month-first \
+       or --date-format day-first"
+        .into(),
+    ),
+  }
+}
+
+/// Reads lines like `inc 20250310T090000Z - 20250310T092500Z # tomato`, the
+/// first tag being the project. Intervals still open are left out
+fn parse_timewarrior(
+  content: &str,
+) -> Result<Vec<ImportedSession>, Box<dyn Error>> {
+  let format =
+    format_description!("[year][month][day]T[hour][minute][second]Z");
+  let mut sessions = vec![];
+
+  for line in content.lines() {
+    let Some(interval) = line.trim().strip_prefix("inc ") else {
+      continue;
+    };
+    let (range, tags) = interval.split_once(" # ").unwrap_or((interval, ""));
+    let Some((start, end)) = range.trim().split_once(" - ") else {
+      continue;
+    };
+    // Annotations follow the tags after another #
+    let tags = tags.split(" # ").next().unwrap_or_default();
+
+    sessions.push(ImportedSession {
+      project: parse_timewarrior_tags(tags).into_iter().next(),
+      start: PrimitiveDateTime::parse(start.trim(), &format)?.assume_utc(),
+      end: PrimitiveDateTime::parse(end.trim(), &format)?.assume_utc(),
+    });
+  }
+  Ok(sessions)
+}
+
+/// Tags are separated by spaces, quoted when they hold some
+fn parse_timewarrior_tags(tags: &str) -> Vec<String> {
+  let mut result = vec![];
+  let mut tag = String::new();
+  let mut quoted = false;
+  let mut chars = tags.chars();
+
+  while let Some(char) = chars.next() {
+    match char {
+      '\\' if quoted => tag.extend(chars.next()),
+      '"' => quoted = !quoted,
+      ' ' if !quoted => {
+        if !tag.is_empty() {
+          result.push(std::mem::take(&mut tag));
+        }
+      }
+      _ => tag.push(char),
+    }
+  }
+  if !tag.is_empty() {
+    result.push(tag);
+  }
+  result
+}
+
+fn parse_date_time(
+  date: &str,
+  time: &str,
+  date_format: DateFormat,
+) -> Result<PrimitiveDateTime, Box<dyn Error>> {
+  let date = date_format
+    .parse(date)
+    .ok_or_else(|| format!("Unknown date format: {}", date))?;
+  let time = TIME_FORMATS
+    .iter()
+    .find_map(|format| Time::parse(time, format).ok())
+    .ok_or_else(|| format!("Unknown time format: {}", time))?;
+  Ok(PrimitiveDateTime::new(date, time))
+}
+
+/// Splits CSV content in rows of fields, quoted fields may hold separators,
+/// doubled quotes and line breaks
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+  let mut rows = vec![];
+  let mut row = vec![];
+  let mut field = String::new();
+  let mut quoted = false;
+  let mut chars = content.chars().peekable();
+
+  while let Some(char) = chars.next() {
+    match char {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      }
+      '"' => quoted = !quoted,
+      ',' if !quoted => row.push(std::mem::take(&mut field)),
+      '\r' if !quoted => {}
+      '\n' if !quoted => {
+        row.push(std::mem::take(&mut field));
+        rows.push(std::mem::take(&mut row));
+      }
+      _ => field.push(char),
+    }
+  }
+  if !field.is_empty() || !row.is_empty() {
+    row.push(field);
+    rows.push(row);
+  }
+  rows
+}
+
+/// One line per session for the dry run preview
+pub fn format_session(session: &ImportedSession) -> String {
+  let start = LocalZone.to_local(session.start);
+  let format = format_description!("[year]-[month]-[day] [hour]:[minute]");
+  let duration = (session.end - session.start).whole_seconds().max(0);
+
+  format!(
+    "{} {} - {}",
+    start.format(&format).unwrap_or_default(),
+    session.project.as_deref().unwrap_or("Unassigned"),
+    render_timer_seconds(duration as u32)
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::repository::Repository;
+  use time::macros::{datetime, offset};
+
+  #[test]
+  fn test_parse_toggl_csv() {
+    let content = "User,Email,Client,Project,Task,Description,Billable,\
+      Start date,Start time,End date,End time,Duration,Tags\n\
+      Ann,ann@example.com,,\"Website, v2\",,\"Fix \"\"nav\"\"\",No,\
+      2025-03-10,09:00:00,2025-03-10,09:25:00,00:25:00,\n\
+      Ann,ann@example.com,,,,Email,No,\
+      2025-03-10,23:50:00,2025-03-11,00:10:00,00:20:00,\n";
+
+    let sessions = parse_csv_report(content, None, offset!(+1)).unwrap();
+
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].project.as_deref(), Some("Website, v2"));
+    assert_eq!(sessions[0].start, datetime!(2025-03-10 09:00 +01:00));
+    assert_eq!(sessions[0].end, datetime!(2025-03-10 09:25 +01:00));
+    assert_eq!(sessions[1].project, None);
+    assert_eq!(sessions[1].end, datetime!(2025-03-11 00:10 +01:00));
+  }
+
+  #[test]
+  fn test_parse_clockify_csv() {
+    let content = "\u{feff}\"Project\",\"Client\",\"Description\",\
+      \"Start Date\",\"Start Time\",\"End Date\",\"End Time\"\r\n\
+      \"Tomato\",\"\",\"Import\",\"03/10/2025\",\"01:30:00 PM\",\
+      \"03/10/2025\",\"02:00:00 PM\"\r\n";
+
+    assert!(parse_csv_report(content, None, offset!(UTC)).is_err());
+    let sessions =
+      parse_csv_report(content, Some(DateFormat::MonthFirst), offset!(UTC))
+        .unwrap();
+
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].project.as_deref(), Some("Tomato"));
+    assert_eq!(sessions[0].start, datetime!(2025-03-10 13:30 UTC));
+    assert_eq!(sessions[0].end, datetime!(2025-03-10 14:00 UTC));
+  }
+
+  #[test]
+  fn test_parse_day_first_csv() {
+    let header = "Project,Start Date,Start Time,End Date,End Time\n";
+    let ambiguous = "Tomato,03/04/2025,09:00,03/04/2025,09:25\n";
+    let content = format!("{header}{ambiguous}");
+
+    let err = parse_csv_report(header, None, offset!(UTC)).unwrap_err();
+    assert_eq!(err.to_string(), "No sessions to import");
+    assert!(parse_csv_report(&content, None, offset!(UTC)).is_err());
+    let sessions =
+      parse_csv_report(&content, Some(DateFormat::DayFirst), offset!(UTC))
+        .unwrap();
+    assert_eq!(sessions[0].start, datetime!(2025-04-03 09:00 UTC));
+
+    let content =
+      format!("{header}{ambiguous}Tomato,25/03/2025,09:00,25/03/2025,09:25\n");
+    let sessions = parse_csv_report(&content, None, offset!(UTC)).unwrap();
+    assert_eq!(sessions[0].start, datetime!(2025-04-03 09:00 UTC));
+    assert_eq!(sessions[1].start, datetime!(2025-03-25 09:00 UTC));
+    assert!(
+      parse_csv_report(&content, Some(DateFormat::MonthFirst), offset!(UTC))
+        .is_err()
+    );
+  }
+
+  #[test]
+  fn test_parse_timewarrior() {
+    let content = "\
+      inc 20250310T090000Z - 20250310T092500Z # \"big project\" focus\n\
+      inc 20250310T100000Z - 20250310T103000Z\n\
+      inc 20250310T110000Z # still running\n";
+
+    let sessions = parse_timewarrior(content).unwrap();
+
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].project.as_deref(), Some("big project"));
+    assert_eq!(sessions[0].start, datetime!(2025-03-10 09:00 UTC));
+    assert_eq!(sessions[0].end, datetime!(2025-03-10 09:25 UTC));
+    assert_eq!(sessions[1].project, None);
+  }
+
+  #[test]
+  fn test_import_sessions() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    let sessions = vec![
+      ImportedSession {
+        project: Some(String::from("tomato")),
+        start: datetime!(2025-03-10 09:00 UTC),
+        end: datetime!(2025-03-10 09:25 UTC),
+      },
+      ImportedSession {
+        project: Some(String::from("Website")),
+        start: datetime!(2025-03-10 10:00 UTC),
+        end: datetime!(2025-03-10 10:30 UTC),
+      },
+    ];
+
+    let preview = repo.import_sessions(&sessions, true).unwrap();
+    assert_eq!(preview.imported, 2);
+    assert_eq!(preview.new_projects, vec![String::from("Website")]);
+    assert_eq!(repo.get_projects_in_progress().unwrap().len(), 1);
+
+    let summary = repo.import_sessions(&sessions, false).unwrap();
+    assert_eq!(summary.imported, 2);
+    assert_eq!(summary.duration, 3300);
+    let projects = repo.get_projects_in_progress().unwrap();
+    assert_eq!(projects[0].time_spent, 1500);
+    assert_eq!(projects[0].work_sessions, 1);
+    assert_eq!(projects[1].name, "Website");
+    assert_eq!(projects[1].time_spent, 1800);
+
+    let again = repo.import_sessions(&sessions, false).unwrap();
+    assert_eq!(again.imported, 0);
+    assert_eq!(again.skipped, 2);
+  }
+}
@@ -1,21 +1,102 @@
+use clap::Parser;
 use color_eyre;
+use std::io::{self, Write};
+use std::path::Path;
 
 mod app;
+mod cli;
+mod daemon;
 mod errors;
+mod export;
+mod import;
 mod repository;
 mod structs;
 mod tui;
 mod utils;
 mod widgets;
 
-use structs::{App, UserConfig};
+use cli::Cli;
+use repository::Repository;
+use structs::{App, UserConfig, UserState};
 
 fn main() -> color_eyre::Result<()> {
-  errors::install_hooks()?;
-  let mut terminal = tui::init()?;
+  let cli = Cli::parse();
   let user_config: UserConfig = confy::load("tomato", "config")
     .expect("Error when loading the config file");
-  App::new(&user_config).run(&mut terminal)?;
+  let mut user_state: UserState =
+    confy::load("tomato", "state").unwrap_or_default();
+  if let Some(command) = cli.command {
+    // Commands would create an empty database where the data is expected
+    if let Some(last_location) = get_moved_db(&user_config, &user_state) {
+      eprintln!(
+        "The database location changed, run tomato without a command to \
+         copy the one at {}",
+        last_location
+      );
+      std::process::exit(1);
+    }
+    if let Err(err) = cli::run(command, &user_config) {
+      eprintln!("{}", err);
+      std::process::exit(1);
+    }
+    return Ok(());
+  }
+
+  errors::install_hooks()?;
+  offer_db_copy(&user_config, &user_state);
+
+  let _lock = daemon::TuiLock::acquire()
+    .inspect_err(|_| utils::notify("Error when locking the session"));
+  let mut terminal = tui::init()?;
+  let mut app = App::new(&user_config);
+  user_state.last_db_location = Some(user_config.db_location.clone());
+  if confy::store("tomato", "state", &user_state).is_err() {
+    utils::notify("Error when saving the state file");
+  }
+  app.run(&mut terminal)?;
   tui::restore()?;
   Ok(())
 }
+
+/// The previous database location, when db_location changed and the data
+/// is still only there
+fn get_moved_db<'a>(
+  user_config: &UserConfig,
+  user_state: &'a UserState,
+) -> Option<&'a str> {
+  let last_location = user_state.last_db_location.as_deref()?;
+  if last_location == user_config.db_location
+    || Path::new(&user_config.db_location).exists()
+    || !Path::new(last_location).exists()
+  {
+    return None;
+  }
+  Some(last_location)
+}
+
+/// When db_location changed, the data can be brought from the previous
+/// location before an empty database is created at the new one
+fn offer_db_copy(user_config: &UserConfig, user_state: &UserState) {
+  let Some(last_location) = get_moved_db(user_config, user_state) else {
+    return;
+  };
+
+  println!("The database location changed.");
+  println!("A database exists at {}", last_location);
+  print!("Copy it to {}? [y/N] ", user_config.db_location);
+  let _ = io::stdout().flush();
+  let mut answer = String::new();
+  if io::stdin().read_line(&mut answer).is_err()
+    || !answer.trim().eq_ignore_ascii_case("y")
+  {
+    return;
+  }
+
+  match Repository::copy_database(last_location, &user_config.db_location) {
+    Ok(()) => println!("Database copied, the old one was left in place."),
+    Err(err) => {
+      println!("The database could not be copied: {}", err);
+      std::process::exit(1);
+    }
+  }
+}
@@ -1,11 +1,38 @@
+mod migrations;
+
 use crate::app::options::Options;
-use crate::structs::{Project, SessionPerDay, UserConfig};
-use rusqlite::{Connection, Result};
+use crate::structs::{
+  DaySummary, DayTotal, ImportSummary, ImportedSession, Project,
+  ProjectDurations, ProjectFilter, ProjectTotal, Session, SessionFilter,
+  SessionPerDay, SessionRecord, SessionType, Task, UserConfig,
+};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput};
+use rusqlite::{Connection, OptionalExtension, Result, ToSql, Transaction};
 use std::error::Error;
-use std::fs::create_dir_all;
+use std::fs::{copy, create_dir_all, remove_file};
 use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use time::{Date, OffsetDateTime};
+
+use crate::utils::{TimeZone, to_local_date_time};
+
+/// Columns read by `to_task`, with the work sessions spent on the task
+const TASK_COLUMNS: &str = "task.id, task.project_id, task.name, task.done,
+  task.estimate, task.selected, (
+    SELECT COUNT(*) FROM session
+    WHERE session.task_id = task.id AND session_type = 'work'
+  ), (
+    SELECT COALESCE(SUM(duration), 0) FROM session
+    WHERE session.task_id = task.id AND session_type = 'work'
+  )";
 
-use time::Date;
+/// Columns read by `to_project`, the tags of the project last
+const PROJECT_COLUMNS: &str = "project.*, (
+  SELECT GROUP_CONCAT(tag.name) FROM project_tag
+  JOIN tag ON tag.id = project_tag.tag_id
+  WHERE project_tag.project_id = project.id
+)";
 
 pub struct Repository {
   connection: Connection,
@@ -25,41 +52,51 @@ impl Repository {
     let connection = Connection::open(config.db_location.clone())
       .map_err(|e| e.to_string())?;
 
-    connection.execute(
-      "CREATE TABLE IF NOT EXISTS project (
-            id INTEGER PRIMARY KEY,
-            name TEXT NOT NULL,
-            selected BOOLEAN NOT NULL DEFAULT FALSE,
-            time_spent INTEGER NOT NULL DEFAULT 0,
-            work_sessions INTEGER NOT NULL DEFAULT 0,
-            finished BOOLEAN NOT NULL DEFAULT FALSE,
-            creation_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            modification_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-        );",
-      (),
-    )?;
+    let version = migrations::get_version(&connection)?;
+    if version < migrations::LATEST_VERSION
+      && !migrations::is_empty(&connection)?
+    {
+      let backup = format!("{}.v{}.bak", config.db_location, version);
+      copy(&config.db_location, backup)?;
+    }
 
-    connection.execute(
-      "CREATE TABLE IF NOT EXISTS session (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            project_id INTEGER NOT NULL,
-            date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            duration INTEGER NOT NULL DEFAULT 0
-        );",
-      (),
-    )?;
+    Ok(Self::from_connection(connection)?)
+  }
 
-    connection.execute(
-      "CREATE TABLE IF NOT EXISTS options (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            work_duration INTEGER DEFAULT 25,
-            break_duration INTEGER DEFAULT 5,
-            ask_before_work BOOLEAN NOT NULL DEFAULT FALSE,
-            ask_before_break BOOLEAN NOT NULL DEFAULT TRUE
-        );",
-      (),
-    )?;
+  /// Copies a database to a location with no database yet, the copy is
+  /// checked before being kept
+  pub fn copy_database(from: &str, to: &str) -> Result<(), Box<dyn Error>> {
+    if Path::new(to).exists() {
+      return Err(format!("A file already exists at {}", to).into());
+    }
+    if let Some(folder) = Path::new(to).parent() {
+      create_dir_all(folder)?;
+    }
 
+    let source = Connection::open(from)?;
+    source.execute("VACUUM INTO ?1", [to])?;
+
+    let copied = Connection::open(to)?;
+    let integrity: String =
+      copied.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+    let count_sessions = "SELECT COUNT(*) FROM session";
+    let sessions: u32 =
+      source.query_row(count_sessions, [], |row| row.get(0))?;
+    let copied_sessions: u32 =
+      copied.query_row(count_sessions, [], |row| row.get(0))?;
+    drop(copied);
+
+    if integrity != "ok" || sessions != copied_sessions {
+      remove_file(to)?;
+      return Err("The copied database is corrupted".into());
+    }
+    Ok(())
+  }
+
+  pub fn from_connection(
+    mut connection: Connection,
+  ) -> Result<Self, rusqlite::Error> {
+    migrations::migrate(&mut connection)?;
     Ok(Self { connection })
   }
 
@@ -70,9 +107,12 @@ impl Repository {
             work_duration, 
             break_duration, 
             ask_before_work, 
-            ask_before_break
+            ask_before_break,
+            long_break_duration,
+            sessions_before_long_break,
+            heatmap_threshold
         ) 
-        VALUES (1, 25, 5, false, false)",
+        VALUES (1, 25, 5, false, false, 15, 4, 120)",
       (),
     )?;
 
@@ -85,40 +125,48 @@ impl Repository {
           break_duration: row.get(2)?,
           ask_before_work: row.get(3)?,
           ask_before_break: row.get(4)?,
+          long_break_duration: row.get(5)?,
+          sessions_before_long_break: row.get(6)?,
+          heatmap_threshold: row.get(7)?,
         })
       })
   }
 
+  /// Random id told apart from other databases, e.g. in calendar exports
+  pub fn get_database_id(&self) -> Result<String, rusqlite::Error> {
+    self.create_of_get_options()?;
+    self.connection.execute(
+      "UPDATE options SET database_id = lower(hex(randomblob(8)))
+        WHERE id = 1 AND database_id IS NULL",
+      (),
+    )?;
+    self.connection.query_row(
+      "SELECT database_id FROM options WHERE id = 1",
+      [],
+      |row| row.get(0),
+    )
+  }
+
   pub fn update_options(
     &self,
     opts: Options,
   ) -> Result<usize, rusqlite::Error> {
     self.connection.execute(
-            "UPDATE options SET work_duration = ?2, break_duration = ?3, ask_before_work = ?4, ask_before_break = ?5 WHERE id = ?1",
-            (opts.id, opts.work_duration, opts.break_duration, opts.ask_before_work, opts.ask_before_break)
+            "UPDATE options SET work_duration = ?2, break_duration = ?3, ask_before_work = ?4, ask_before_break = ?5, long_break_duration = ?6, sessions_before_long_break = ?7, heatmap_threshold = ?8 WHERE id = ?1",
+            (opts.id, opts.work_duration, opts.break_duration, opts.ask_before_work, opts.ask_before_break, opts.long_break_duration, opts.sessions_before_long_break, opts.heatmap_threshold)
         )
   }
 
   pub fn get_projects_in_progress(
     &self,
   ) -> Result<Vec<Project>, rusqlite::Error> {
-    let mut stmt = self.connection.prepare(
-      "SELECT * FROM project WHERE finished = false ORDER BY project.id ASC",
-    )?;
+    let mut stmt = self.connection.prepare(&format!(
+      "SELECT {} FROM project WHERE finished = false ORDER BY project.id ASC",
+      PROJECT_COLUMNS
+    ))?;
 
     stmt
-      .query_map([], |row| {
-        Ok(Project {
-          id: row.get(0)?,
-          name: row.get(1)?,
-          selected: row.get(2)?,
-          time_spent: row.get(3)?,
-          work_sessions: row.get(4)?,
-          finished: row.get(5)?,
-          creation_date: row.get(6)?,
-          modification_date: row.get(7)?,
-        })
-      })?
+      .query_map([], to_project)?
       .collect::<Result<Vec<_>, _>>()
   }
 
@@ -146,34 +194,448 @@ impl Repository {
     id: usize,
   ) -> Result<(), rusqlite::Error> {
     self.connection.execute(
-      "UPDATE project SET finished = true WHERE id = ?1",
+      "UPDATE project SET finished = true,
+        finish_date = CURRENT_TIMESTAMP WHERE id = ?1",
       [&id.to_string()],
     )?;
     Ok(())
   }
 
-  pub fn add_session(
+  /// Sessions of every type tied to a project and their work time
+  pub fn get_project_usage(
+    &self,
+    id: usize,
+  ) -> Result<(u32, u32), rusqlite::Error> {
+    self.connection.query_row(
+      "SELECT COUNT(*), COALESCE(SUM(
+          CASE WHEN session_type = 'work' THEN duration ELSE 0 END
+        ), 0)
+        FROM session WHERE project_id = ?1",
+      [id],
+      |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+  }
+
+  /// Deletes a project along with its sessions
+  pub fn delete_project(&mut self, id: usize) -> Result<(), rusqlite::Error> {
+    let tx = self.connection.transaction()?;
+    tx.execute("DELETE FROM session WHERE project_id = ?1", [id])?;
+    tx.execute(
+      "UPDATE active_session SET project_id = NULL, task_id = NULL
+        WHERE project_id = ?1",
+      [id],
+    )?;
+    tx.execute("DELETE FROM task WHERE project_id = ?1", [id])?;
+    tx.execute("DELETE FROM project_tag WHERE project_id = ?1", [id])?;
+    tx.execute("DELETE FROM project WHERE id = ?1", [id])?;
+    delete_unused_tags(&tx)?;
+    tx.commit()
+  }
+
+  /// Deletes a project, its sessions and time going to another project, or
+  /// to none
+  pub fn merge_project(
     &mut self,
+    id: usize,
+    into: Option<usize>,
+  ) -> Result<(), rusqlite::Error> {
+    let tx = self.connection.transaction()?;
+    tx.execute(
+      "UPDATE project SET
+          time_spent = project.time_spent + source.time_spent,
+          work_sessions = project.work_sessions + source.work_sessions,
+          modification_date = CURRENT_TIMESTAMP
+        FROM (SELECT time_spent, work_sessions FROM project WHERE id = ?1)
+          AS source
+        WHERE project.id = ?2",
+      (id, into),
+    )?;
+    // Tasks follow their sessions, without a project left they're dropped
+    for table in ["session", "active_session"] {
+      tx.execute(
+        &format!(
+          "UPDATE {} SET project_id = ?2,
+            task_id = CASE WHEN ?2 IS NOT NULL THEN task_id END
+            WHERE project_id = ?1",
+          table
+        ),
+        (id, into),
+      )?;
+    }
+    match into {
+      Some(into) => tx.execute(
+        "UPDATE task SET project_id = ?2, selected = false
+          WHERE project_id = ?1",
+        (id, into),
+      )?,
+      None => tx.execute("DELETE FROM task WHERE project_id = ?1", [id])?,
+    };
+    tx.execute("DELETE FROM project_tag WHERE project_id = ?1", [id])?;
+    tx.execute("DELETE FROM project WHERE id = ?1", [id])?;
+    delete_unused_tags(&tx)?;
+    tx.commit()
+  }
+
+  pub fn set_project_estimate(
+    &self,
+    id: usize,
+    estimate: Option<u32>,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "UPDATE project SET estimate = ?1 WHERE id = ?2",
+      (estimate, id),
+    )?;
+    Ok(())
+  }
+
+  pub fn set_project_durations(
+    &self,
+    id: usize,
+    durations: ProjectDurations,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "UPDATE project SET work_duration = ?1, break_duration = ?2,
+        long_break_duration = ?3 WHERE id = ?4",
+      (
+        durations.work,
+        durations.short_break,
+        durations.long_break,
+        id,
+      ),
+    )?;
+    Ok(())
+  }
+
+  /// Finished projects that had an estimate, the last finished first
+  pub fn get_estimated_projects(
+    &self,
+    tag: Option<&str>,
+  ) -> Result<Vec<Project>, rusqlite::Error> {
+    let mut stmt = self.connection.prepare(&format!(
+      "SELECT {} FROM project
+        WHERE finished = true AND estimate IS NOT NULL
+        AND (?1 IS NULL OR project.id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?1
+        ))
+        ORDER BY finish_date DESC, project.id DESC",
+      PROJECT_COLUMNS
+    ))?;
+
+    stmt
+      .query_map([tag], to_project)?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Replaces the tags of a project, the new ones get created
+  pub fn set_project_tags(
+    &mut self,
+    id: usize,
+    tags: &[String],
+  ) -> Result<(), rusqlite::Error> {
+    let tx = self.connection.transaction()?;
+    tx.execute("DELETE FROM project_tag WHERE project_id = ?1", [id])?;
+    for tag in tags {
+      tx.execute("INSERT OR IGNORE INTO tag (name) VALUES (?1)", [tag])?;
+      tx.execute(
+        "INSERT OR IGNORE INTO project_tag (project_id, tag_id)
+          SELECT ?1, id FROM tag WHERE name = ?2",
+        (id, tag),
+      )?;
+    }
+    delete_unused_tags(&tx)?;
+    tx.commit()
+  }
+
+  /// Tags of the projects, finished or not
+  pub fn get_tags(&self) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = self
+      .connection
+      .prepare("SELECT name FROM tag ORDER BY name ASC")?;
+
+    stmt
+      .query_map([], |row| row.get(0))?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Tasks of a project, the ones left to do first
+  pub fn get_tasks(
+    &self,
+    project_id: usize,
+  ) -> Result<Vec<Task>, rusqlite::Error> {
+    let mut stmt = self.connection.prepare(&format!(
+      "SELECT {} FROM task WHERE project_id = ?1 ORDER BY done ASC, id ASC",
+      TASK_COLUMNS
+    ))?;
+
+    stmt
+      .query_map([project_id], to_task)?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  pub fn get_selected_task(&self) -> Result<Option<Task>, rusqlite::Error> {
+    self
+      .connection
+      .query_row(
+        &format!("SELECT {} FROM task WHERE selected = true", TASK_COLUMNS),
+        [],
+        to_task,
+      )
+      .optional()
+  }
+
+  pub fn add_task(
+    &self,
     project_id: usize,
+    name: &str,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "INSERT INTO task (project_id, name) VALUES (?1, ?2)",
+      (project_id, name),
+    )?;
+    Ok(())
+  }
+
+  pub fn update_task(
+    &self,
+    id: usize,
+    name: &str,
+  ) -> Result<(), rusqlite::Error> {
+    self
+      .connection
+      .execute("UPDATE task SET name = ?1 WHERE id = ?2", (name, id))?;
+    Ok(())
+  }
+
+  /// A task done can't stay selected
+  pub fn set_task_done(
+    &self,
+    id: usize,
+    done: bool,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "UPDATE task SET done = ?1, selected = selected AND NOT ?1
+        WHERE id = ?2",
+      (done, id),
+    )?;
+    Ok(())
+  }
+
+  pub fn set_task_estimate(
+    &self,
+    id: usize,
+    estimate: Option<u32>,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "UPDATE task SET estimate = ?1 WHERE id = ?2",
+      (estimate, id),
+    )?;
+    Ok(())
+  }
+
+  /// Only one task is selected at a time, none when `id` is None
+  pub fn set_selected_task(
+    &mut self,
+    id: Option<usize>,
+  ) -> Result<(), rusqlite::Error> {
+    let tx = self.connection.transaction()?;
+    tx.execute("UPDATE task SET selected = false WHERE selected = true", [])?;
+    tx.execute("UPDATE task SET selected = true WHERE id = ?1", [id])?;
+    tx.commit()
+  }
+
+  /// Finished projects, the last finished first
+  pub fn get_finished_projects(&self) -> Result<Vec<Project>, rusqlite::Error> {
+    let mut stmt = self.connection.prepare(&format!(
+      "SELECT {} FROM project WHERE finished = true
+        ORDER BY finish_date DESC, project.id DESC",
+      PROJECT_COLUMNS
+    ))?;
+
+    stmt
+      .query_map([], to_project)?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Brings a finished project back in progress
+  pub fn mark_project_unfinished(
+    &mut self,
+    id: usize,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "UPDATE project SET finished = false, finish_date = NULL WHERE id = ?1",
+      [id],
+    )?;
+    Ok(())
+  }
+
+  pub fn add_session(
+    &mut self,
+    session: &Session,
     duration: u32,
+    completed: bool,
   ) -> Result<(), rusqlite::Error> {
     let tx = self.connection.transaction()?;
-    let dur = duration as usize;
-    tx.execute(
-      "INSERT INTO session (project_id, duration) VALUES (?1, ?2);",
-      [&project_id, &dur],
+    let start = to_local_date_time(session.start);
+    let end = to_local_date_time(session.end.unwrap_or(SystemTime::now()));
+    insert_session(
+      &tx,
+      NewSession {
+        project_id: session.project_id,
+        task_id: session.task_id,
+        duration,
+        session_type: session.session_type,
+        completed,
+        start: &start,
+        end: &end,
+      },
     )?;
+
+    tx.commit()
+  }
+
+  /// Adds a work session done without the timer
+  pub fn add_past_session(
+    &mut self,
+    project_id: Option<usize>,
+    start: OffsetDateTime,
+    duration: u32,
+  ) -> Result<(), rusqlite::Error> {
+    let tx = self.connection.transaction()?;
+    let end = start + time::Duration::seconds(duration as i64);
+    insert_session(
+      &tx,
+      NewSession {
+        project_id,
+        task_id: None,
+        duration,
+        session_type: SessionType::Work,
+        completed: true,
+        start: &start,
+        end: &end,
+      },
+    )?;
+    tx.commit()
+  }
+
+  pub fn get_session_by_id(
+    &self,
+    id: usize,
+  ) -> Result<Option<SessionRecord>, rusqlite::Error> {
+    let request = r#"
+      SELECT session.id, session.project_id, project.name, session_type,
+        start_date, end_date, duration, completed, session.task_id, task.name,
+        session.revision
+      FROM session
+      LEFT JOIN project ON session.project_id = project.id
+      LEFT JOIN task ON session.task_id = task.id
+      WHERE session.id = ?1
+    "#;
+    self
+      .connection
+      .query_row(request, [id], to_session_record)
+      .optional()
+  }
+
+  /// Moves a session to another project or changes its duration, its end
+  /// follows the duration
+  pub fn update_session(
+    &mut self,
+    id: usize,
+    project_id: Option<usize>,
+    duration: u32,
+  ) -> Result<(), rusqlite::Error> {
+    let Some(session) = self.get_session_by_id(id)? else {
+      return Err(rusqlite::Error::QueryReturnedNoRows);
+    };
+    let tx = self.connection.transaction()?;
+    count_session(
+      &tx,
+      session.project_id,
+      session.session_type,
+      session.duration,
+      -1,
+    )?;
+    let end = session.start_date + time::Duration::seconds(duration as i64);
     tx.execute(
-      "UPDATE project SET time_spent = time_spent + ?1, 
-        work_sessions = work_sessions + 1,
-        modification_date = CURRENT_TIMESTAMP 
-        WHERE id = ?2",
-      (&dur, &project_id),
+      "UPDATE session SET project_id = ?1, duration = ?2, end_date = ?3,
+        task_id = CASE WHEN project_id IS ?1 THEN task_id END,
+        revision = revision + 1
+        WHERE id = ?4",
+      (project_id, duration, end, id),
     )?;
+    count_session(&tx, project_id, session.session_type, duration, 1)?;
+    tx.commit()
+  }
 
+  pub fn delete_session(&mut self, id: usize) -> Result<(), rusqlite::Error> {
+    let Some(session) = self.get_session_by_id(id)? else {
+      return Ok(());
+    };
+    let tx = self.connection.transaction()?;
+    count_session(
+      &tx,
+      session.project_id,
+      session.session_type,
+      session.duration,
+      -1,
+    )?;
+    tx.execute("DELETE FROM session WHERE id = ?1", [id])?;
     tx.commit()
   }
 
+  /// Adds work sessions tracked elsewhere, creating their projects when
+  /// missing. The ones already imported, same start and project, are
+  /// skipped. Nothing is written on a dry run, the summary is the same
+  pub fn import_sessions(
+    &mut self,
+    sessions: &[ImportedSession],
+    dry_run: bool,
+  ) -> Result<ImportSummary, rusqlite::Error> {
+    let tx = self.connection.transaction()?;
+    let mut summary = ImportSummary::default();
+
+    for session in sessions {
+      let project_id = match &session.project {
+        Some(name) => Some(get_or_create_project(&tx, name, &mut summary)?),
+        None => None,
+      };
+      let exists: bool = tx.query_row(
+        "SELECT EXISTS (
+          SELECT 1 FROM session
+          WHERE JULIANDAY(start_date) = JULIANDAY(?1) AND project_id IS ?2
+        )",
+        (&session.start, project_id),
+        |row| row.get(0),
+      )?;
+      if exists {
+        summary.skipped += 1;
+        continue;
+      }
+
+      let duration = (session.end - session.start).whole_seconds().max(0);
+      insert_session(
+        &tx,
+        NewSession {
+          project_id,
+          task_id: None,
+          duration: duration as u32,
+          session_type: SessionType::Work,
+          completed: true,
+          start: &session.start,
+          end: &session.end,
+        },
+      )?;
+      summary.imported += 1;
+      summary.duration += duration as u32;
+    }
+
+    if !dry_run {
+      tx.commit()?;
+    }
+    Ok(summary)
+  }
+
   pub fn set_selected(
     &mut self,
     id: usize,
@@ -203,49 +665,1102 @@ impl Repository {
     &self,
     id: i32,
   ) -> Result<Option<Project>, rusqlite::Error> {
-    let mut stmt = self
-      .connection
-      .prepare("SELECT * FROM project WHERE id = ?1")?;
+    let mut stmt = self.connection.prepare(&format!(
+      "SELECT {} FROM project WHERE id = ?1",
+      PROJECT_COLUMNS
+    ))?;
 
     let mut rows = stmt.query([&id.to_string()])?;
 
     if let Some(row) = rows.next()? {
-      Ok(Some(Project {
-        id: row.get(0)?,
-        name: row.get(1)?,
-        selected: row.get(2)?,
-        time_spent: row.get(3)?,
-        work_sessions: row.get(4)?,
-        finished: row.get(5)?,
-        creation_date: row.get(6)?,
-        modification_date: row.get(7)?,
+      Ok(Some(to_project(row)?))
+    } else {
+      Ok(None)
+    }
+  }
+
+  pub fn save_active_session(
+    &self,
+    session: &Session,
+  ) -> Result<(), rusqlite::Error> {
+    self.connection.execute(
+      "INSERT OR REPLACE INTO active_session (
+            id,
+            session_type,
+            start,
+            duration,
+            project_id,
+            paused_at,
+            paused_time,
+            task_id
+        )
+        VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+      (
+        session.session_type,
+        to_local_date_time(session.start),
+        session.duration,
+        session.project_id,
+        session.paused_at.map(to_local_date_time),
+        session.paused_time.as_secs(),
+        session.task_id,
+      ),
+    )?;
+    Ok(())
+  }
+
+  pub fn get_active_session(&self) -> Result<Option<Session>, rusqlite::Error> {
+    let mut stmt = self.connection.prepare(
+      "SELECT session_type, start, duration, project_id, paused_at, paused_time,
+        task_id
+       FROM active_session WHERE id = 1",
+    )?;
+    let mut rows = stmt.query([])?;
+
+    if let Some(row) = rows.next()? {
+      let start: OffsetDateTime = row.get(1)?;
+      let paused_at: Option<OffsetDateTime> = row.get(4)?;
+      Ok(Some(Session {
+        start: SystemTime::from(start),
+        end: None,
+        duration: row.get(2)?,
+        session_type: row.get(0)?,
+        project_id: row.get(3)?,
+        paused_at: paused_at.map(SystemTime::from),
+        paused_time: Duration::from_secs(row.get(5)?),
+        task_id: row.get(6)?,
       }))
     } else {
       Ok(None)
     }
   }
 
+  pub fn clear_active_session(&self) -> Result<(), rusqlite::Error> {
+    self
+      .connection
+      .execute("DELETE FROM active_session WHERE id = 1", ())?;
+    Ok(())
+  }
+
+  /// Work time per project for a day, sessions belong to the local day
+  /// they started in
   pub fn get_sessions_per_day(
     &self,
     date: &Date,
+    zone: impl TimeZone,
+    tag: Option<&str>,
   ) -> Result<Vec<SessionPerDay>, rusqlite::Error> {
     let request = r#"
-      SELECT project.name AS project_name, DATE(session.date) as date, SUM(duration) AS duration
+      SELECT session.project_id, COALESCE(project.name, 'Unassigned') AS project_name, SUM(duration) AS duration
       FROM session 
-      INNER JOIN project ON session.project_id = project.id
-      WHERE DATE(session.date) = DATE(?1)
-      GROUP BY project_id
+      LEFT JOIN project ON session.project_id = project.id
+      WHERE JULIANDAY(session.start_date) >= JULIANDAY(?1)
+        AND JULIANDAY(session.start_date) < JULIANDAY(?2)
+        AND session.session_type = 'work'
+        AND (?3 IS NULL OR session.project_id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?3
+        ))
+      GROUP BY session.project_id
     "#;
+    let (start, end) = get_day_bounds(date, &zone);
     let mut stmt = self.connection.prepare(request)?;
 
     stmt
-      .query_map([date], |row| {
+      .query_map((start, end, tag), |row| {
         Ok(SessionPerDay {
-          project_name: row.get(0)?,
-          date: row.get(1)?,
+          project_id: row.get(0)?,
+          project_name: row.get(1)?,
+          date: *date,
           duration: row.get(2)?,
         })
       })?
       .collect::<Result<Vec<SessionPerDay>, _>>()
   }
+
+  /// Sessions of every type in the filter, oldest first
+  pub fn get_sessions(
+    &self,
+    filter: &SessionFilter,
+    zone: impl TimeZone,
+  ) -> Result<Vec<SessionRecord>, rusqlite::Error> {
+    let request = r#"
+      SELECT session.id, session.project_id, project.name, session_type,
+        start_date, end_date, duration, completed, session.task_id, task.name,
+        session.revision
+      FROM session
+      LEFT JOIN project ON session.project_id = project.id
+      LEFT JOIN task ON session.task_id = task.id
+      WHERE (?1 IS NULL OR JULIANDAY(start_date) >= JULIANDAY(?1))
+        AND (?2 IS NULL OR JULIANDAY(start_date) < JULIANDAY(?2))
+        AND (?3 IS NULL OR session.project_id IS NULLIF(?3, -1))
+        AND (?4 IS NULL OR session.project_id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?4
+        ))
+      ORDER BY JULIANDAY(start_date) ASC
+    "#;
+    let (from, to) = get_range_bounds(filter, &zone);
+    let mut stmt = self.connection.prepare(request)?;
+
+    stmt
+      .query_map((from, to, filter.project, &filter.tag), to_session_record)?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Work time per project in the filter's range, most worked on first
+  pub fn get_project_totals(
+    &self,
+    filter: &SessionFilter,
+    zone: impl TimeZone,
+  ) -> Result<Vec<ProjectTotal>, rusqlite::Error> {
+    let request = r#"
+      SELECT session.project_id, COALESCE(project.name, 'Unassigned'),
+        SUM(duration) AS total, COUNT(*)
+      FROM session
+      LEFT JOIN project ON session.project_id = project.id
+      WHERE session.session_type = 'work'
+        AND (?1 IS NULL OR JULIANDAY(start_date) >= JULIANDAY(?1))
+        AND (?2 IS NULL OR JULIANDAY(start_date) < JULIANDAY(?2))
+        AND (?3 IS NULL OR session.project_id IS NULLIF(?3, -1))
+        AND (?4 IS NULL OR session.project_id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?4
+        ))
+      GROUP BY session.project_id
+      ORDER BY total DESC
+    "#;
+    let (from, to) = get_range_bounds(filter, &zone);
+    let mut stmt = self.connection.prepare(request)?;
+
+    stmt
+      .query_map((from, to, filter.project, &filter.tag), |row| {
+        Ok(ProjectTotal {
+          project_id: row.get(0)?,
+          project_name: row.get(1)?,
+          duration: row.get(2)?,
+          sessions: row.get(3)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Work time per local day in the filter's range, days without session are
+  /// left out. Sessions are grouped here rather than in SQL since the offset
+  /// of a day depends on its date
+  pub fn get_day_totals(
+    &self,
+    filter: &SessionFilter,
+    zone: impl TimeZone,
+  ) -> Result<Vec<DayTotal>, rusqlite::Error> {
+    let request = r#"
+      SELECT start_date, duration
+      FROM session
+      WHERE session_type = 'work'
+        AND (?1 IS NULL OR JULIANDAY(start_date) >= JULIANDAY(?1))
+        AND (?2 IS NULL OR JULIANDAY(start_date) < JULIANDAY(?2))
+        AND (?3 IS NULL OR project_id IS NULLIF(?3, -1))
+        AND (?4 IS NULL OR project_id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?4
+        ))
+      ORDER BY JULIANDAY(start_date) ASC
+    "#;
+    let (from, to) = get_range_bounds(filter, &zone);
+    let mut stmt = self.connection.prepare(request)?;
+    let sessions = stmt
+      .query_map((from, to, filter.project, &filter.tag), |row| {
+        Ok((row.get::<_, OffsetDateTime>(0)?, row.get::<_, u32>(1)?))
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut totals: Vec<DayTotal> = vec![];
+    for (start, duration) in sessions {
+      let date = zone.to_local(start).date();
+      match totals.last_mut() {
+        Some(total) if total.date == date => {
+          total.duration += duration;
+          total.sessions += 1;
+        }
+        _ => totals.push(DayTotal {
+          date,
+          duration,
+          sessions: 1,
+        }),
+      }
+    }
+    Ok(totals)
+  }
+
+  /// Work time per local day and project in the filter's range
+  pub fn get_day_project_totals(
+    &self,
+    filter: &SessionFilter,
+    zone: impl TimeZone,
+  ) -> Result<Vec<SessionPerDay>, rusqlite::Error> {
+    let request = r#"
+      SELECT start_date, session.project_id,
+        COALESCE(project.name, 'Unassigned'), duration
+      FROM session
+      LEFT JOIN project ON session.project_id = project.id
+      WHERE session.session_type = 'work'
+        AND (?1 IS NULL OR JULIANDAY(start_date) >= JULIANDAY(?1))
+        AND (?2 IS NULL OR JULIANDAY(start_date) < JULIANDAY(?2))
+        AND (?3 IS NULL OR session.project_id IS NULLIF(?3, -1))
+        AND (?4 IS NULL OR session.project_id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?4
+        ))
+    "#;
+    let (from, to) = get_range_bounds(filter, &zone);
+    let mut stmt = self.connection.prepare(request)?;
+    let sessions = stmt
+      .query_map((from, to, filter.project, &filter.tag), |row| {
+        Ok(SessionPerDay {
+          date: zone.to_local(row.get(0)?).date(),
+          project_id: row.get(1)?,
+          project_name: row.get(2)?,
+          duration: row.get(3)?,
+        })
+      })?
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let mut totals: Vec<SessionPerDay> = vec![];
+    for session in sessions {
+      let total = totals.iter_mut().find(|total| {
+        total.date == session.date && total.project_id == session.project_id
+      });
+      match total {
+        Some(total) => total.duration += session.duration,
+        None => totals.push(session),
+      }
+    }
+    totals.sort_by_key(|total| (total.date, total.project_id));
+    Ok(totals)
+  }
+
+  /// Projects finished or not, with a date range only the ones worked on
+  /// during it are kept
+  pub fn get_projects(
+    &self,
+    filter: &SessionFilter,
+    zone: impl TimeZone,
+  ) -> Result<Vec<Project>, rusqlite::Error> {
+    let request = format!(
+      r#"
+      SELECT {} FROM project
+      WHERE (?3 IS NULL OR project.id IS NULLIF(?3, -1))
+        AND ((?1 IS NULL AND ?2 IS NULL) OR EXISTS (
+          SELECT 1 FROM session
+          WHERE session.project_id = project.id
+            AND session.session_type = 'work'
+            AND (?1 IS NULL OR JULIANDAY(start_date) >= JULIANDAY(?1))
+            AND (?2 IS NULL OR JULIANDAY(start_date) < JULIANDAY(?2))
+        ))
+        AND (?4 IS NULL OR project.id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?4
+        ))
+      ORDER BY project.id ASC
+    "#,
+      PROJECT_COLUMNS
+    );
+    let (from, to) = get_range_bounds(filter, &zone);
+    let mut stmt = self.connection.prepare(&request)?;
+
+    stmt
+      .query_map((from, to, filter.project, &filter.tag), to_project)?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Sessions of every type of a project, or of none, started on a local day
+  pub fn get_day_sessions(
+    &self,
+    date: &Date,
+    zone: impl TimeZone,
+    project_id: Option<usize>,
+  ) -> Result<Vec<SessionRecord>, rusqlite::Error> {
+    let request = r#"
+      SELECT session.id, session.project_id, project.name, session_type,
+        start_date, end_date, duration, completed, session.task_id, task.name,
+        session.revision
+      FROM session
+      LEFT JOIN project ON session.project_id = project.id
+      LEFT JOIN task ON session.task_id = task.id
+      WHERE JULIANDAY(start_date) >= JULIANDAY(?1)
+        AND JULIANDAY(start_date) < JULIANDAY(?2)
+        AND session.project_id IS ?3
+      ORDER BY JULIANDAY(start_date) ASC
+    "#;
+    let (start, end) = get_day_bounds(date, &zone);
+    let mut stmt = self.connection.prepare(request)?;
+
+    stmt
+      .query_map((start, end, project_id), to_session_record)?
+      .collect::<Result<Vec<_>, _>>()
+  }
+
+  /// Work sessions of the day since the last long break
+  pub fn get_cycle_count(
+    &self,
+    date: &Date,
+    zone: impl TimeZone,
+  ) -> Result<u32, rusqlite::Error> {
+    let request = r#"
+      SELECT COUNT(*)
+      FROM session
+      WHERE session_type = 'work'
+        AND JULIANDAY(start_date) >= JULIANDAY(?1)
+        AND JULIANDAY(start_date) < JULIANDAY(?2)
+        AND id > COALESCE(
+          (SELECT MAX(id) FROM session WHERE session_type = 'long_break'),
+          0
+        )
+    "#;
+
+    self
+      .connection
+      .query_row(request, get_day_bounds(date, &zone), |row| row.get(0))
+  }
+
+  pub fn get_day_summary(
+    &self,
+    date: &Date,
+    zone: impl TimeZone,
+    tag: Option<&str>,
+  ) -> Result<DaySummary, rusqlite::Error> {
+    let request = r#"
+      SELECT
+        COALESCE(SUM(CASE WHEN session_type = 'work' THEN duration END), 0),
+        COALESCE(SUM(CASE WHEN session_type != 'work' THEN duration END), 0),
+        COUNT(CASE WHEN session_type = 'work' THEN 1 END),
+        COUNT(CASE WHEN session_type = 'work' AND completed THEN 1 END)
+      FROM session
+      WHERE JULIANDAY(session.start_date) >= JULIANDAY(?1)
+        AND JULIANDAY(session.start_date) < JULIANDAY(?2)
+        AND (?3 IS NULL OR session.project_id IN (
+          SELECT project_id FROM project_tag
+          JOIN tag ON tag.id = project_tag.tag_id WHERE tag.name = ?3
+        ))
+    "#;
+    let (start, end) = get_day_bounds(date, &zone);
+
+    self
+      .connection
+      .query_row(request, (start, end, tag), |row| {
+        Ok(DaySummary {
+          focus_time: row.get(0)?,
+          break_time: row.get(1)?,
+          work_sessions: row.get(2)?,
+          completed_sessions: row.get(3)?,
+        })
+      })
+  }
+}
+
+impl ToSql for SessionType {
+  fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+    Ok(ToSqlOutput::from(self.as_str()))
+  }
+}
+
+/// NULL matches every project and -1 the sessions without one, see
+/// `session.project_id IS NULLIF(?, -1)`
+impl ToSql for ProjectFilter {
+  fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+    Ok(match self {
+      ProjectFilter::All => ToSqlOutput::from(rusqlite::types::Null),
+      ProjectFilter::Unassigned => ToSqlOutput::from(-1),
+      ProjectFilter::Project(id) => ToSqlOutput::from(*id as i64),
+    })
+  }
+}
+
+impl FromSql for SessionType {
+  fn column_result(
+    value: rusqlite::types::ValueRef<'_>,
+  ) -> FromSqlResult<Self> {
+    value
+      .as_str()?
+      .parse()
+      .map_err(|err: String| FromSqlError::Other(err.into()))
+  }
+}
+
+/// Start and end instants of a local day, a DST change makes it 23 or 25
+/// hours long. Timestamps are compared with JULIANDAY so rows saved with any
+/// offset, or none for the ones written by CURRENT_TIMESTAMP, are normalised
+/// to UTC
+fn get_day_bounds(
+  date: &Date,
+  zone: &impl TimeZone,
+) -> (OffsetDateTime, OffsetDateTime) {
+  let start = zone.assume(date.midnight());
+  let end = match date.next_day() {
+    Some(next_day) => zone.assume(next_day.midnight()),
+    None => start + time::Duration::DAY,
+  };
+  (start, end)
+}
+
+/// Row of the session table, its project counts it when it's work
+struct NewSession<'a> {
+  project_id: Option<usize>,
+  task_id: Option<usize>,
+  duration: u32,
+  session_type: SessionType,
+  completed: bool,
+  start: &'a OffsetDateTime,
+  end: &'a OffsetDateTime,
+}
+
+/// Inserts a session, work sessions count as time spent on their project
+fn insert_session(
+  tx: &Transaction,
+  session: NewSession,
+) -> Result<(), rusqlite::Error> {
+  tx.execute(
+    "INSERT INTO session (
+          project_id,
+          task_id,
+          duration,
+          session_type,
+          completed,
+          start_date,
+          end_date
+      )
+      VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
+    (
+      session.project_id,
+      session.task_id,
+      session.duration,
+      session.session_type,
+      session.completed,
+      session.start,
+      session.end,
+    ),
+  )?;
+  count_session(
+    tx,
+    session.project_id,
+    session.session_type,
+    session.duration,
+    1,
+  )
+}
+
+/// Adds the time of a work session to its project, or takes it back with a
+/// sign of -1
+fn count_session(
+  tx: &Transaction,
+  project_id: Option<usize>,
+  session_type: SessionType,
+  duration: u32,
+  sign: i64,
+) -> Result<(), rusqlite::Error> {
+  if let (Some(project_id), SessionType::Work) = (project_id, session_type) {
+    tx.execute(
+      "UPDATE project SET time_spent = time_spent + ?1,
+        work_sessions = work_sessions + ?2,
+        modification_date = CURRENT_TIMESTAMP
+        WHERE id = ?3",
+      (sign * duration as i64, sign, project_id),
+    )?;
+  }
+  Ok(())
+}
+
+fn to_session_record(row: &rusqlite::Row) -> Result<SessionRecord> {
+  Ok(SessionRecord {
+    id: row.get(0)?,
+    project_id: row.get(1)?,
+    project_name: row.get(2)?,
+    session_type: row.get(3)?,
+    start_date: row.get(4)?,
+    end_date: row.get(5)?,
+    duration: row.get(6)?,
+    completed: row.get(7)?,
+    task_id: row.get(8)?,
+    task_name: row.get(9)?,
+    revision: row.get(10)?,
+  })
+}
+
+fn to_project(row: &rusqlite::Row) -> Result<Project, rusqlite::Error> {
+  Ok(Project {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    selected: row.get(2)?,
+    time_spent: row.get(3)?,
+    work_sessions: row.get(4)?,
+    finished: row.get(5)?,
+    creation_date: row.get(6)?,
+    modification_date: row.get(7)?,
+    finish_date: row.get(8)?,
+    estimate: row.get(9)?,
+    durations: ProjectDurations {
+      work: row.get(10)?,
+      short_break: row.get(11)?,
+      long_break: row.get(12)?,
+    },
+    tags: get_tags_column(row.get(13)?),
+  })
+}
+
+fn to_task(row: &rusqlite::Row) -> Result<Task, rusqlite::Error> {
+  Ok(Task {
+    id: row.get(0)?,
+    project_id: row.get(1)?,
+    name: row.get(2)?,
+    done: row.get(3)?,
+    estimate: row.get(4)?,
+    selected: row.get(5)?,
+    work_sessions: row.get(6)?,
+    time_spent: row.get(7)?,
+  })
+}
+
+/// Tags come joined by commas, which tag names can't hold
+fn get_tags_column(tags: Option<String>) -> Vec<String> {
+  let mut tags: Vec<String> = tags
+    .unwrap_or_default()
+    .split(',')
+    .filter(|tag| !tag.is_empty())
+    .map(String::from)
+    .collect();
+  tags.sort_by_key(|tag| tag.to_lowercase());
+  tags
+}
+
+/// Tags no project uses anymore aren't offered as filters
+fn delete_unused_tags(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  tx.execute(
+    "DELETE FROM tag WHERE id NOT IN (SELECT tag_id FROM project_tag)",
+    (),
+  )?;
+  Ok(())
+}
+
+/// Id of the project with this name, in progress ones first, a new project
+/// is created when none matches
+fn get_or_create_project(
+  tx: &Transaction,
+  name: &str,
+  summary: &mut ImportSummary,
+) -> Result<usize, rusqlite::Error> {
+  let id = tx
+    .query_row(
+      "SELECT id FROM project WHERE name = ?1 COLLATE NOCASE
+        ORDER BY finished ASC, id ASC LIMIT 1",
+      [name],
+      |row| row.get(0),
+    )
+    .optional()?;
+  if let Some(id) = id {
+    return Ok(id);
+  }
+
+  tx.execute("INSERT INTO project (name) VALUES (?1)", [name])?;
+  summary.new_projects.push(name.to_string());
+  Ok(tx.last_insert_rowid() as usize)
+}
+
+/// Bounds of the filter's date range, the end of the last day included
+fn get_range_bounds(
+  filter: &SessionFilter,
+  zone: &impl TimeZone,
+) -> (Option<OffsetDateTime>, Option<OffsetDateTime>) {
+  let from = filter.from.map(|date| get_day_bounds(&date, zone).0);
+  let to = filter.to.map(|date| get_day_bounds(&date, zone).1);
+  (from, to)
+}
+
+#[cfg(test)]
+impl Repository {
+  pub fn in_memory() -> Self {
+    Self::from_connection(Connection::open_in_memory().unwrap()).unwrap()
+  }
+
+  /// Saves a session completed after its whole duration
+  pub fn add_finished_session(&mut self, session: Session) {
+    self
+      .add_session(&session, session.duration * 60, true)
+      .unwrap();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use time::UtcOffset;
+  use time::macros::{date, datetime, offset};
+
+  fn get_focus_time(repo: &Repository, date: Date, offset: UtcOffset) -> u32 {
+    repo
+      .get_day_summary(&date, offset, None)
+      .unwrap()
+      .focus_time
+  }
+
+  #[test]
+  fn test_sessions_per_local_day() {
+    let mut repo = Repository::in_memory();
+    // 21:30 UTC on the 10th, already the 11th in UTC+3
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-10 23:30 +02:00),
+    ));
+
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 10), offset!(+2)),
+      1500
+    );
+    assert_eq!(get_focus_time(&repo, date!(2025 - 03 - 11), offset!(+2)), 0);
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 10), offset!(UTC)),
+      1500
+    );
+    assert_eq!(get_focus_time(&repo, date!(2025 - 03 - 10), offset!(+3)), 0);
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 11), offset!(+3)),
+      1500
+    );
+  }
+
+  #[test]
+  fn test_sessions_per_day_negative_offset() {
+    let mut repo = Repository::in_memory();
+    // 03:00 UTC on the 11th
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-10 22:00 -05:00),
+    ));
+
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 10), offset!(-5)),
+      1500
+    );
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 11), offset!(UTC)),
+      1500
+    );
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 10), offset!(UTC)),
+      0
+    );
+  }
+
+  #[test]
+  fn test_session_across_midnight_counts_on_start_day() {
+    let mut repo = Repository::in_memory();
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      30,
+      datetime!(2025-03-10 23:50 +09:00),
+    ));
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-11 00:10 +09:00),
+    ));
+
+    let day =
+      repo.get_sessions_per_day(&date!(2025 - 03 - 10), offset!(+9), None);
+    let day = day.unwrap();
+    assert_eq!(day.len(), 1);
+    assert_eq!(day[0].project_name, "Unassigned");
+    assert_eq!(day[0].duration, 1800);
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 11), offset!(+9)),
+      1500
+    );
+  }
+
+  #[test]
+  fn test_utc_timestamps_without_offset() {
+    let repo = Repository::in_memory();
+    // Rows written with CURRENT_TIMESTAMP have no offset and are in UTC
+    repo
+      .connection
+      .execute(
+        "INSERT INTO session (duration, start_date, end_date)
+          VALUES (1500, '2025-03-10 23:30:00', '2025-03-10 23:55:00')",
+        (),
+      )
+      .unwrap();
+
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 10), offset!(UTC)),
+      1500
+    );
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 11), offset!(+1)),
+      1500
+    );
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 10), offset!(-1)),
+      1500
+    );
+    assert_eq!(get_focus_time(&repo, date!(2025 - 03 - 10), offset!(+1)), 0);
+  }
+
+  #[test]
+  fn test_copy_database() {
+    let folder = std::env::temp_dir()
+      .join(format!("tomato-copy-test-{}", std::process::id()));
+    let from = folder.join("old/tomato.sqlite");
+    let to = folder.join("new/tomato.sqlite");
+    let (from, to) = (from.to_str().unwrap(), to.to_str().unwrap());
+    let mut repo = create_repo_at(from);
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-10 10:00 UTC),
+    ));
+    drop(repo);
+
+    Repository::copy_database(from, to).unwrap();
+    let copied = create_repo_at(to);
+    assert_eq!(
+      get_focus_time(&copied, date!(2025 - 03 - 10), offset!(UTC)),
+      1500
+    );
+    assert!(Repository::copy_database(from, to).is_err());
+
+    std::fs::remove_dir_all(folder).unwrap();
+  }
+
+  fn create_repo_at(db_location: &str) -> Repository {
+    let config = UserConfig {
+      db_location: db_location.to_string(),
+    };
+    Repository::new(&config).unwrap()
+  }
+
+  #[test]
+  fn test_day_totals_in_local_days() {
+    let mut repo = Repository::in_memory();
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-10 09:00 UTC),
+    ));
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-10 23:30 UTC),
+    ));
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-12 09:00 UTC),
+    ));
+    let filter = SessionFilter {
+      from: Some(date!(2025 - 03 - 10)),
+      to: Some(date!(2025 - 03 - 11)),
+      ..Default::default()
+    };
+
+    let totals = repo.get_day_totals(&filter, offset!(+2)).unwrap();
+
+    assert_eq!(
+      totals,
+      vec![
+        DayTotal {
+          date: date!(2025 - 03 - 10),
+          duration: 1500,
+          sessions: 1,
+        },
+        DayTotal {
+          date: date!(2025 - 03 - 11),
+          duration: 1500,
+          sessions: 1,
+        },
+      ]
+    );
+  }
+
+  /// Paris in 2025, on summer time from March 30 at 01:00 UTC
+  struct Paris;
+
+  impl TimeZone for Paris {
+    fn offset_at(&self, instant: OffsetDateTime) -> UtcOffset {
+      if instant < datetime!(2025-03-30 01:00 UTC) {
+        offset!(+1)
+      } else {
+        offset!(+2)
+      }
+    }
+  }
+
+  #[test]
+  fn test_local_days_across_dst_change() {
+    let mut repo = Repository::in_memory();
+    // 00:30 on the 30th in winter time, then 00:30 on April 1 in summer time
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-29 23:30 UTC),
+    ));
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-31 22:30 UTC),
+    ));
+    let filter = SessionFilter {
+      from: Some(date!(2025 - 03 - 30)),
+      to: Some(date!(2025 - 04 - 01)),
+      ..Default::default()
+    };
+
+    let days: Vec<Date> = repo
+      .get_day_totals(&filter, Paris)
+      .unwrap()
+      .iter()
+      .map(|total| total.date)
+      .collect();
+    assert_eq!(days, vec![date!(2025 - 03 - 30), date!(2025 - 04 - 01)]);
+    let days = repo.get_day_project_totals(&filter, Paris).unwrap();
+    assert_eq!(days[1].date, date!(2025 - 04 - 01));
+
+    let summary = |date| repo.get_day_summary(&date, Paris, None).unwrap();
+    assert_eq!(summary(date!(2025 - 03 - 31)).focus_time, 0);
+    assert_eq!(summary(date!(2025 - 04 - 01)).focus_time, 1500);
+    // The winter offset of today would put it on March 31
+    assert_eq!(
+      get_focus_time(&repo, date!(2025 - 03 - 31), offset!(+1)),
+      1500
+    );
+
+    let (start, end) = get_day_bounds(&date!(2025 - 03 - 30), &Paris);
+    assert_eq!(start, datetime!(2025-03-29 23:00 UTC));
+    assert_eq!(end, datetime!(2025-03-30 22:00 UTC));
+  }
+
+  #[test]
+  fn test_day_sessions_of_a_project() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    repo.add_finished_session(Session::finished(
+      SessionType::Work,
+      25,
+      datetime!(2025-03-10 10:00 UTC),
+    ));
+    let start = datetime!(2025-03-10 09:00 UTC);
+    let session = Session::finished(SessionType::Break, 5, start);
+    repo.add_finished_session(session.with_project(1));
+
+    let sessions = repo
+      .get_day_sessions(&date!(2025 - 03 - 10), offset!(UTC), Some(1))
+      .unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert!(matches!(sessions[0].session_type, SessionType::Break));
+    assert_eq!(sessions[0].project_name.as_deref(), Some("Tomato"));
+
+    let unassigned = repo
+      .get_day_sessions(&date!(2025 - 03 - 10), offset!(UTC), None)
+      .unwrap();
+    assert_eq!(unassigned.len(), 1);
+    assert_eq!(unassigned[0].duration, 1500);
+  }
+
+  #[test]
+  fn test_session_crud_keeps_projects_in_sync() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    repo.add_project("Website").unwrap();
+    let get_project = |repo: &Repository, id| {
+      let project = repo.get_project_by_id(id).unwrap().unwrap();
+      (project.time_spent, project.work_sessions)
+    };
+
+    repo
+      .add_past_session(Some(1), datetime!(2025-03-10 09:00 UTC), 1500)
+      .unwrap();
+    assert_eq!(get_project(&repo, 1), (1500, 1));
+
+    repo.update_session(1, Some(2), 600).unwrap();
+    assert_eq!(get_project(&repo, 1), (0, 0));
+    assert_eq!(get_project(&repo, 2), (600, 1));
+    let session = repo.get_session_by_id(1).unwrap().unwrap();
+    assert_eq!(session.end_date, Some(datetime!(2025-03-10 09:10 UTC)));
+
+    repo.delete_session(1).unwrap();
+    assert_eq!(get_project(&repo, 2), (0, 0));
+    assert!(repo.get_session_by_id(1).unwrap().is_none());
+  }
+
+  #[test]
+  fn test_finish_and_unfinish_project() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    repo.add_project("Website").unwrap();
+
+    repo.mark_project_finished(1).unwrap();
+    assert_eq!(repo.get_projects_in_progress().unwrap().len(), 1);
+    let finished = repo.get_finished_projects().unwrap();
+    assert_eq!(finished.len(), 1);
+    assert_eq!(finished[0].name, "Tomato");
+    assert!(finished[0].finish_date.is_some());
+
+    repo.mark_project_unfinished(1).unwrap();
+    assert!(repo.get_finished_projects().unwrap().is_empty());
+    let projects = repo.get_projects_in_progress().unwrap();
+    assert_eq!(projects.len(), 2);
+    assert_eq!(projects[0].finish_date, None);
+  }
+
+  #[test]
+  fn test_estimates_of_finished_projects() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    repo.add_project("Website").unwrap();
+    repo.add_project("Course").unwrap();
+    repo.set_project_estimate(1, Some(1)).unwrap();
+    repo.set_project_estimate(3, Some(4)).unwrap();
+    repo
+      .set_project_tags(3, &[String::from("learning")])
+      .unwrap();
+    for hour in [9, 10] {
+      let start = datetime!(2025-03-10 00:00 UTC).replace_hour(hour).unwrap();
+      repo.add_past_session(Some(1), start, 1500).unwrap();
+    }
+    for id in 1..=3 {
+      repo.mark_project_finished(id).unwrap();
+    }
+
+    let projects = repo.get_estimated_projects(None).unwrap();
+    assert_eq!(projects.len(), 2);
+    let tomato = projects.iter().find(|p| p.id == 1).unwrap();
+    assert_eq!((tomato.work_sessions, tomato.estimate), (2, Some(1)));
+    assert!(tomato.is_over_estimate());
+    let learning = repo.get_estimated_projects(Some("learning")).unwrap();
+    assert_eq!(learning.len(), 1);
+    assert_eq!(learning[0].name, "Course");
+
+    repo.set_project_estimate(1, None).unwrap();
+    assert_eq!(repo.get_estimated_projects(None).unwrap().len(), 1);
+  }
+
+  #[test]
+  fn test_project_durations() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Deep work").unwrap();
+    repo.set_project_tags(1, &[String::from("client")]).unwrap();
+    let durations = ProjectDurations {
+      work: Some(50),
+      short_break: Some(10),
+      long_break: None,
+    };
+    repo.set_project_durations(1, durations).unwrap();
+
+    let project = repo.get_project_by_id(1).unwrap().unwrap();
+    assert_eq!(project.durations, durations);
+    assert_eq!(project.tags, vec!["client"]);
+
+    repo
+      .set_project_durations(1, ProjectDurations::default())
+      .unwrap();
+    let project = repo.get_project_by_id(1).unwrap().unwrap();
+    assert_eq!(project.durations.work, None);
+  }
+
+  #[test]
+  fn test_delete_or_merge_project() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    repo.add_project("Website").unwrap();
+    repo.add_project("Typo").unwrap();
+    repo
+      .add_past_session(Some(1), datetime!(2025-03-10 09:00 UTC), 1500)
+      .unwrap();
+    repo
+      .add_past_session(Some(3), datetime!(2025-03-10 10:00 UTC), 1500)
+      .unwrap();
+    repo
+      .add_past_session(Some(3), datetime!(2025-03-10 11:00 UTC), 600)
+      .unwrap();
+    assert_eq!(repo.get_project_usage(3).unwrap(), (2, 2100));
+
+    repo.merge_project(3, Some(1)).unwrap();
+    assert!(repo.get_project_by_id(3).unwrap().is_none());
+    let project = repo.get_project_by_id(1).unwrap().unwrap();
+    assert_eq!((project.time_spent, project.work_sessions), (3600, 3));
+    assert_eq!(repo.get_project_usage(1).unwrap(), (3, 3600));
+
+    repo.delete_project(1).unwrap();
+    assert!(repo.get_project_by_id(1).unwrap().is_none());
+    let filter = SessionFilter::default();
+    assert!(repo.get_sessions(&filter, offset!(UTC)).unwrap().is_empty());
+    assert_eq!(repo.get_projects_in_progress().unwrap().len(), 1);
+  }
+
+  #[test]
+  fn test_tags_filter_totals() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Website").unwrap();
+    repo.add_project("Course").unwrap();
+    let tags = |tags: &[&str]| -> Vec<String> {
+      tags.iter().map(|t| t.to_string()).collect()
+    };
+    repo.set_project_tags(1, &tags(&["client", "web"])).unwrap();
+    repo.set_project_tags(2, &tags(&["learning"])).unwrap();
+    repo
+      .add_past_session(Some(1), datetime!(2025-03-10 09:00 UTC), 1500)
+      .unwrap();
+    repo
+      .add_past_session(Some(2), datetime!(2025-03-10 10:00 UTC), 600)
+      .unwrap();
+
+    let project = repo.get_project_by_id(1).unwrap().unwrap();
+    assert_eq!(project.tags, vec!["client", "web"]);
+    assert_eq!(repo.get_tags().unwrap(), vec!["client", "learning", "web"]);
+
+    let filter = SessionFilter {
+      tag: Some(String::from("Client")),
+      ..Default::default()
+    };
+    let totals = repo.get_day_totals(&filter, offset!(UTC)).unwrap();
+    assert_eq!(totals[0].duration, 1500);
+    let projects = repo.get_projects(&filter, offset!(UTC)).unwrap();
+    assert_eq!(projects.len(), 1);
+    let day = date!(2025 - 03 - 10);
+    let summary = repo.get_day_summary(&day, offset!(UTC), Some("learning"));
+    assert_eq!(summary.unwrap().focus_time, 600);
+
+    repo.set_project_tags(1, &tags(&["client"])).unwrap();
+    assert_eq!(repo.get_tags().unwrap(), vec!["client", "learning"]);
+  }
+
+  #[test]
+  fn test_tasks_track_their_sessions() {
+    let mut repo = Repository::in_memory();
+    repo.add_project("Tomato").unwrap();
+    repo.add_project("Website").unwrap();
+    repo.add_task(1, "Tasks").unwrap();
+    repo.add_task(1, "Docs").unwrap();
+    repo.set_task_estimate(1, Some(4)).unwrap();
+    repo.set_selected_task(Some(1)).unwrap();
+
+    let start = datetime!(2025-03-10 09:00 UTC);
+    let session = Session::finished(SessionType::Work, 25, start);
+    repo.add_finished_session(session.with_project(1).with_task(1));
+
+    let tasks = repo.get_tasks(1).unwrap();
+    assert_eq!(tasks[0].estimate, Some(4));
+    assert_eq!((tasks[0].work_sessions, tasks[0].time_spent), (1, 1500));
+    assert_eq!(tasks[1].work_sessions, 0);
+    assert_eq!(repo.get_selected_task().unwrap().unwrap().id, 1);
+
+    repo.set_task_done(1, true).unwrap();
+    assert!(repo.get_selected_task().unwrap().is_none());
+    assert_eq!(repo.get_tasks(1).unwrap()[0].name, "Docs");
+
+    repo.merge_project(1, Some(2)).unwrap();
+    let tasks = repo.get_tasks(2).unwrap();
+    assert_eq!(tasks.len(), 2);
+    repo.delete_project(2).unwrap();
+    assert!(repo.get_tasks(2).unwrap().is_empty());
+  }
 }
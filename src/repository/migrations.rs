@@ -0,0 +1,391 @@
+use rusqlite::{Connection, Transaction};
+
+type Migration = fn(&Transaction) -> Result<(), rusqlite::Error>;
+
+/// Schema upgrades in order, the version of a database is the number of steps
+/// already applied and is kept in `PRAGMA user_version`. Steps only ever get
+/// appended to this list.
+const MIGRATIONS: [Migration; 12] = [
+  create_tables,
+  add_long_break_options,
+  create_active_session,
+  add_session_type,
+  add_session_dates,
+  add_heatmap_threshold,
+  add_project_finish_date,
+  create_tags,
+  create_tasks,
+  add_project_estimate,
+  add_project_durations,
+  add_export_ids,
+];
+
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+pub fn get_version(connection: &Connection) -> Result<u32, rusqlite::Error> {
+  connection.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Databases created by tomato 1.1.0 and older have tables but no version
+pub fn is_empty(connection: &Connection) -> Result<bool, rusqlite::Error> {
+  let tables: u32 = connection.query_row(
+    "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+    [],
+    |row| row.get(0),
+  )?;
+  Ok(tables == 0)
+}
+
+/// Applies the missing steps, each one in its own transaction with the version
+/// bump so an interrupted upgrade resumes where it stopped
+pub fn migrate(connection: &mut Connection) -> Result<(), rusqlite::Error> {
+  let version = get_version(connection)?;
+
+  for (index, migration) in MIGRATIONS.iter().enumerate().skip(version as usize)
+  {
+    let tx = connection.transaction()?;
+    migration(&tx)?;
+    tx.pragma_update(None, "user_version", index + 1)?;
+    tx.commit()?;
+  }
+
+  Ok(())
+}
+
+/// Schema of tomato 1.1.0, tables may already exist since it wasn't versioned
+fn create_tables(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  tx.execute_batch(
+    "CREATE TABLE IF NOT EXISTS project (
+          id INTEGER PRIMARY KEY,
+          name TEXT NOT NULL,
+          selected BOOLEAN NOT NULL DEFAULT FALSE,
+          time_spent INTEGER NOT NULL DEFAULT 0,
+          work_sessions INTEGER NOT NULL DEFAULT 0,
+          finished BOOLEAN NOT NULL DEFAULT FALSE,
+          creation_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+          modification_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+      );
+      CREATE TABLE IF NOT EXISTS session (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          project_id INTEGER NOT NULL,
+          date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+          duration INTEGER NOT NULL DEFAULT 0
+      );
+      CREATE TABLE IF NOT EXISTS options (
+          id INTEGER PRIMARY KEY CHECK (id = 1),
+          work_duration INTEGER DEFAULT 25,
+          break_duration INTEGER DEFAULT 5,
+          ask_before_work BOOLEAN NOT NULL DEFAULT FALSE,
+          ask_before_break BOOLEAN NOT NULL DEFAULT TRUE
+      );",
+  )
+}
+
+fn add_long_break_options(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(
+    tx,
+    "options",
+    "long_break_duration",
+    "INTEGER DEFAULT 15",
+  )?;
+  add_column_if_missing(
+    tx,
+    "options",
+    "sessions_before_long_break",
+    "INTEGER DEFAULT 4",
+  )
+}
+
+/// Only one session can run at a time, it's kept until it ends so it can be
+/// recovered after a crash
+fn create_active_session(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  tx.execute_batch(
+    "CREATE TABLE IF NOT EXISTS active_session (
+          id INTEGER PRIMARY KEY CHECK (id = 1),
+          session_type TEXT NOT NULL,
+          start TEXT NOT NULL,
+          duration INTEGER NOT NULL,
+          project_id INTEGER,
+          paused_at TEXT,
+          paused_time INTEGER NOT NULL DEFAULT 0
+      );",
+  )
+}
+
+/// Sessions used to be work sessions always tied to a project, the table has
+/// to be rebuilt to make project_id nullable
+fn add_session_type(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  if has_column(tx, "session", "session_type")? {
+    return Ok(());
+  }
+
+  tx.execute_batch(
+    "ALTER TABLE session RENAME TO session_old;
+      CREATE TABLE session (
+          id INTEGER PRIMARY KEY AUTOINCREMENT,
+          project_id INTEGER,
+          date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+          duration INTEGER NOT NULL DEFAULT 0,
+          session_type TEXT NOT NULL DEFAULT 'work',
+          completed BOOLEAN NOT NULL DEFAULT TRUE
+      );
+      INSERT INTO session (id, project_id, date, duration)
+          SELECT id, project_id, date, duration FROM session_old;
+      DROP TABLE session_old;",
+  )
+}
+
+/// Sessions used to only keep the date they were saved at, which is when they
+/// ended
+fn add_session_dates(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(tx, "session", "start_date", "TEXT")?;
+  add_column_if_missing(tx, "session", "end_date", "TEXT")?;
+  tx.execute(
+    "UPDATE session SET
+          start_date = DATETIME(date, '-' || duration || ' seconds'),
+          end_date = date
+      WHERE start_date IS NULL",
+    (),
+  )?;
+  Ok(())
+}
+
+/// Minutes of focus a day needs for the strongest color of the calendar
+fn add_heatmap_threshold(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(
+    tx,
+    "options",
+    "heatmap_threshold",
+    "INTEGER DEFAULT 120",
+  )
+}
+
+/// Projects finished before this step get their last modification as finish
+/// date, the best guess available
+fn add_project_finish_date(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(tx, "project", "finish_date", "TEXT")?;
+  tx.execute(
+    "UPDATE project SET finish_date = modification_date
+        WHERE finished = true AND finish_date IS NULL",
+    (),
+  )?;
+  Ok(())
+}
+
+/// Projects can carry several tags, names are unique whatever their case
+fn create_tags(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  tx.execute_batch(
+    "CREATE TABLE IF NOT EXISTS tag (
+          id INTEGER PRIMARY KEY,
+          name TEXT NOT NULL UNIQUE COLLATE NOCASE
+      );
+      CREATE TABLE IF NOT EXISTS project_tag (
+          project_id INTEGER NOT NULL,
+          tag_id INTEGER NOT NULL,
+          PRIMARY KEY (project_id, tag_id)
+      );",
+  )
+}
+
+/// Tasks split a project, sessions may go to one of them
+fn create_tasks(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  tx.execute_batch(
+    "CREATE TABLE IF NOT EXISTS task (
+          id INTEGER PRIMARY KEY,
+          project_id INTEGER NOT NULL,
+          name TEXT NOT NULL,
+          done BOOLEAN NOT NULL DEFAULT FALSE,
+          estimate INTEGER,
+          selected BOOLEAN NOT NULL DEFAULT FALSE,
+          creation_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+      );",
+  )?;
+  add_column_if_missing(tx, "session", "task_id", "INTEGER")?;
+  add_column_if_missing(tx, "active_session", "task_id", "INTEGER")
+}
+
+/// Pomodoros a project should take, tasks have their own
+fn add_project_estimate(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(tx, "project", "estimate", "INTEGER")
+}
+
+/// Durations in minutes replacing the options while the project is selected
+fn add_project_durations(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(tx, "project", "work_duration", "INTEGER")?;
+  add_column_if_missing(tx, "project", "break_duration", "INTEGER")?;
+  add_column_if_missing(tx, "project", "long_break_duration", "INTEGER")
+}
+
+/// Calendar events exported from different databases must not share their
+/// UID, and they change with the revision of their session
+fn add_export_ids(tx: &Transaction) -> Result<(), rusqlite::Error> {
+  add_column_if_missing(tx, "options", "database_id", "TEXT")?;
+  add_column_if_missing(tx, "session", "revision", "INTEGER NOT NULL DEFAULT 0")
+}
+
+fn has_column(
+  connection: &Connection,
+  table: &str,
+  column: &str,
+) -> Result<bool, rusqlite::Error> {
+  let mut stmt =
+    connection.prepare(&format!("PRAGMA table_info({})", table))?;
+  let exists = stmt
+    .query_map([], |row| row.get::<_, String>(1))?
+    .collect::<Result<Vec<_>, _>>()?
+    .iter()
+    .any(|name| name == column);
+
+  Ok(exists)
+}
+
+/// Some databases were upgraded by builds predating versioned migrations
+fn add_column_if_missing(
+  connection: &Connection,
+  table: &str,
+  column: &str,
+  definition: &str,
+) -> Result<(), rusqlite::Error> {
+  if !has_column(connection, table, column)? {
+    connection.execute(
+      &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+      (),
+    )?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::repository::Repository;
+  use crate::structs::UserConfig;
+  use std::path::Path;
+
+  /// Database as tomato 1.1.0 left it, with some data
+  fn create_v1_1_0_database(connection: &Connection) {
+    connection
+      .execute_batch(
+        "CREATE TABLE project (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            selected BOOLEAN NOT NULL DEFAULT FALSE,
+            time_spent INTEGER NOT NULL DEFAULT 0,
+            work_sessions INTEGER NOT NULL DEFAULT 0,
+            finished BOOLEAN NOT NULL DEFAULT FALSE,
+            creation_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            modification_date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE session (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            date TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            duration INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE options (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            work_duration INTEGER DEFAULT 25,
+            break_duration INTEGER DEFAULT 5,
+            ask_before_work BOOLEAN NOT NULL DEFAULT FALSE,
+            ask_before_break BOOLEAN NOT NULL DEFAULT TRUE
+        );
+        INSERT INTO project (name, time_spent, work_sessions)
+            VALUES ('tomato', 1500, 1);
+        INSERT INTO session (project_id, date, duration)
+            VALUES (1, '2025-03-10 10:25:00', 1500);
+        INSERT INTO options VALUES (1, 50, 10, TRUE, FALSE);",
+      )
+      .unwrap();
+  }
+
+  #[test]
+  fn test_upgrade_from_v1_1_0() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    create_v1_1_0_database(&connection);
+    assert_eq!(get_version(&connection).unwrap(), 0);
+    assert!(!is_empty(&connection).unwrap());
+
+    migrate(&mut connection).unwrap();
+    assert_eq!(get_version(&connection).unwrap(), LATEST_VERSION);
+
+    let session: (usize, String, bool, String, String) = connection
+      .query_row(
+        "SELECT project_id, session_type, completed, start_date, end_date
+          FROM session WHERE id = 1",
+        [],
+        |row| {
+          Ok((
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+          ))
+        },
+      )
+      .unwrap();
+    assert_eq!(
+      session,
+      (
+        1,
+        String::from("work"),
+        true,
+        String::from("2025-03-10 10:00:00"),
+        String::from("2025-03-10 10:25:00"),
+      )
+    );
+
+    let options: (u32, u32, u32, u32) = connection
+      .query_row(
+        "SELECT work_duration, break_duration, long_break_duration,
+          sessions_before_long_break FROM options",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+      )
+      .unwrap();
+    assert_eq!(options, (50, 10, 15, 4));
+
+    // Sessions without project are allowed from now on
+    connection
+      .execute("INSERT INTO session (duration) VALUES (300)", ())
+      .unwrap();
+  }
+
+  #[test]
+  fn test_migrate_new_database() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    assert!(is_empty(&connection).unwrap());
+
+    migrate(&mut connection).unwrap();
+    assert_eq!(get_version(&connection).unwrap(), LATEST_VERSION);
+    assert!(has_column(&connection, "session", "start_date").unwrap());
+    assert!(has_column(&connection, "active_session", "paused_at").unwrap());
+
+    // Nothing left to apply
+    migrate(&mut connection).unwrap();
+    assert_eq!(get_version(&connection).unwrap(), LATEST_VERSION);
+  }
+
+  #[test]
+  fn test_backup_before_upgrade() {
+    let folder =
+      std::env::temp_dir().join(format!("tomato-test-{}", std::process::id()));
+    std::fs::create_dir_all(&folder).unwrap();
+    let db_location = folder.join("tomato.sqlite");
+    let db_location = db_location.to_str().unwrap().to_string();
+    create_v1_1_0_database(&Connection::open(&db_location).unwrap());
+
+    let config = UserConfig {
+      db_location: db_location.clone(),
+    };
+    Repository::new(&config).unwrap();
+    let backup = format!("{}.v0.bak", db_location);
+    assert!(Path::new(&backup).exists());
+
+    let backup_connection = Connection::open(&backup).unwrap();
+    assert_eq!(get_version(&backup_connection).unwrap(), 0);
+    assert!(!has_column(&backup_connection, "session", "start_date").unwrap());
+
+    std::fs::remove_dir_all(folder).unwrap();
+  }
+}
@@ -3,9 +3,11 @@ use dirs::data_dir;
 use ratatui::widgets::ListState;
 use serde::{Deserialize, Serialize};
 use std::default::Default;
-use std::time::SystemTime;
-use time::Date;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use time::{Date, OffsetDateTime, Time};
 
+use crate::daemon::client::DaemonClient;
 use crate::repository::Repository;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,14 +29,24 @@ impl Default for UserConfig {
   }
 }
 
+/// What tomato remembers between runs, kept apart from the user config
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserState {
+  pub last_db_location: Option<String>,
+}
+
 pub struct App {
   pub state: State,
   pub exit: bool,
   pub current_session: Option<Session>,
+  /// Set when a daemon runs, it then owns the session
+  pub daemon: Option<DaemonClient>,
   pub input: String,
   pub repo: Repository,
   pub projects_list: ProjectsList,
   pub calendar: CalendarState,
+  pub report: ReportState,
   pub options: OptionsState,
 }
 
@@ -43,6 +55,29 @@ pub struct ProjectsList {
   pub projects: Vec<Project>,
   pub selected_id: Option<usize>,
   pub state: ListState,
+  /// Listing the finished projects instead of the ones in progress
+  pub show_finished: bool,
+  /// Only listing the projects with this tag
+  pub tag_filter: Option<String>,
+  /// Project about to be deleted
+  pub deletion: Option<ProjectDeletion>,
+  /// Tasks of the highlighted project
+  pub tasks: Vec<Task>,
+  pub task_state: ListState,
+  /// Moving through the tasks instead of the projects
+  pub tasks_focused: bool,
+  /// Task the work sessions go to, one of the selected project
+  pub selected_task: Option<Task>,
+}
+
+/// What deleting a project loses, shown before confirming
+#[derive(Debug)]
+pub struct ProjectDeletion {
+  pub project_id: usize,
+  pub name: String,
+  pub sessions: u32,
+  /// Work time in seconds
+  pub duration: u32,
 }
 
 #[derive(Debug)]
@@ -61,14 +96,89 @@ impl Default for CalendarSection {
 pub struct CalendarState {
   pub selected_date: Option<Date>,
   pub sessions: Vec<SessionPerDay>,
+  pub summary: DaySummary,
+  /// Work time of the days of the selected month, for the heatmap
+  pub month_totals: Vec<DayTotal>,
+  /// Work time per day and project of the selected week, for the chart
+  pub week_totals: Vec<SessionPerDay>,
+  /// Entry of `sessions` showing its sessions below it
+  pub expanded: Option<usize>,
+  pub expanded_sessions: Vec<SessionRecord>,
+  /// Only counting the projects with this tag
+  pub tag_filter: Option<String>,
+  /// Session being added or edited from the list
+  pub draft: Option<SessionDraft>,
   pub list_state: ListState,
   pub selected_section: CalendarSection,
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Session added or edited from the calendar, filled one input at a time
+#[derive(Debug, Default)]
+pub struct SessionDraft {
+  /// None for a session being added
+  pub id: Option<usize>,
+  pub project_id: Option<usize>,
+  pub start: Option<Time>,
+}
+
+/// A line of the calendar list, a project entry or one of the sessions of the
+/// expanded entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CalendarRow {
+  Project(usize),
+  Session(usize),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReportPeriod {
+  #[default]
+  Week,
+  Month,
+}
+
+#[derive(Default)]
+pub struct ReportState {
+  pub period: ReportPeriod,
+  /// Any day of the period shown
+  pub date: Option<Date>,
+  pub projects: Vec<ProjectTotal>,
+  /// Every day of the period, the ones without session included
+  pub days: Vec<DayTotal>,
+  /// Only counting the projects with this tag
+  pub tag_filter: Option<String>,
+  /// Finished projects that had an estimate
+  pub estimates: Vec<Project>,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SessionType {
   Work,
   Break,
+  LongBreak,
+}
+
+impl SessionType {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SessionType::Work => "work",
+      SessionType::Break => "break",
+      SessionType::LongBreak => "long_break",
+    }
+  }
+}
+
+impl FromStr for SessionType {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    match value {
+      "work" => Ok(SessionType::Work),
+      "break" => Ok(SessionType::Break),
+      "long_break" => Ok(SessionType::LongBreak),
+      _ => Err(format!("Unknown session type: {}", value)),
+    }
+  }
 }
 
 #[derive(Debug)]
@@ -79,16 +189,35 @@ pub enum State {
   ConfirmBreak,
   ConfirmWork,
   ConfirmFinished,
+  ConfirmRecover,
   ChooseTime,
+  ExportInput,
   WorkInput,
   BreakInput,
   ProjectsList,
   ProjectsInputAdd,
   ProjectsInputUpdate,
+  ProjectsInputTags,
+  ProjectsInputEstimate,
+  ProjectsInputDurations,
+  TaskInputAdd,
+  TaskInputUpdate,
+  TaskInputEstimate,
+  ConfirmDeleteProject,
+  ReassignProjectInput,
   Calendar,
+  SessionProjectInput,
+  SessionStartInput,
+  SessionDurationInput,
+  ConfirmDeleteSession,
+  Report,
+  EstimatesReport,
   Options,
   WorkDurationInput,
   BreakDurationInput,
+  LongBreakDurationInput,
+  SessionsBeforeLongBreakInput,
+  HeatmapThresholdInput,
 }
 
 impl Default for State {
@@ -97,32 +226,224 @@ impl Default for State {
   }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
   pub start: SystemTime,
   pub end: Option<SystemTime>,
   pub duration: u32,
   pub session_type: SessionType,
+  pub project_id: Option<usize>,
+  pub paused_at: Option<SystemTime>,
+  pub paused_time: Duration,
+  /// Missing from the requests of older clients
+  #[serde(default)]
+  pub task_id: Option<usize>,
 }
 
 #[derive(Debug)]
 pub struct SessionPerDay {
+  pub project_id: Option<usize>,
+  pub project_name: String,
+  pub date: Date,
+  pub duration: u32,
+}
+
+/// Work time spent on a project over several days
+#[derive(Debug)]
+pub struct ProjectTotal {
+  pub project_id: Option<usize>,
   pub project_name: String,
+  pub duration: u32,
+  pub sessions: u32,
+}
+
+/// Work time of a local day
+#[derive(Debug, Clone, PartialEq)]
+pub struct DayTotal {
   pub date: Date,
   pub duration: u32,
+  pub sessions: u32,
+}
+
+/// A row of the session table, as exported
+#[derive(Debug)]
+pub struct SessionRecord {
+  pub id: usize,
+  pub project_id: Option<usize>,
+  pub project_name: Option<String>,
+  pub session_type: SessionType,
+  pub start_date: OffsetDateTime,
+  pub end_date: Option<OffsetDateTime>,
+  pub duration: u32,
+  pub completed: bool,
+  pub task_id: Option<usize>,
+  pub task_name: Option<String>,
+  /// Incremented each time the session is edited
+  pub revision: u32,
+}
+
+/// A work session tracked by another tool
+#[derive(Debug)]
+pub struct ImportedSession {
+  pub project: Option<String>,
+  pub start: OffsetDateTime,
+  pub end: OffsetDateTime,
+}
+
+/// What an import added, or would add on a dry run
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+  pub imported: u32,
+  /// Sessions already in the database
+  pub skipped: u32,
+  pub duration: u32,
+  pub new_projects: Vec<String>,
+}
+
+/// Which sessions to pick, both dates are included and unset fields don't
+/// filter anything
+#[derive(Debug, Default)]
+pub struct SessionFilter {
+  pub from: Option<Date>,
+  pub to: Option<Date>,
+  pub project: ProjectFilter,
+  /// Only the projects with this tag
+  pub tag: Option<String>,
+}
+
+/// Sessions of any project, of none or of a single one
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ProjectFilter {
+  #[default]
+  All,
+  Unassigned,
+  Project(usize),
+}
+
+/// Focus and break time of a day, with how many work sessions ran to the end
+#[derive(Debug, Default)]
+pub struct DaySummary {
+  pub focus_time: u32,
+  pub break_time: u32,
+  pub work_sessions: u32,
+  pub completed_sessions: u32,
 }
 
 impl Session {
-  pub fn new(session_type: SessionType, duration: u32) -> Self {
+  pub fn new(
+    session_type: SessionType,
+    duration: u32,
+    project_id: Option<usize>,
+  ) -> Self {
     Self {
       start: SystemTime::now(),
       end: None,
       duration,
       session_type,
+      project_id,
+      paused_at: None,
+      paused_time: Duration::ZERO,
+      task_id: None,
+    }
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused_at.is_some()
+  }
+
+  pub fn toggle_pause(&mut self) {
+    match self.paused_at {
+      None => self.paused_at = Some(SystemTime::now()),
+      Some(paused_at) => {
+        self.paused_time += elapsed_since(paused_at);
+        self.paused_at = None;
+      }
+    }
+  }
+
+  /// Total time spent paused, including the ongoing pause if any
+  pub fn get_paused_time(&self) -> Duration {
+    match self.paused_at {
+      None => self.paused_time,
+      Some(paused_at) => self.paused_time + elapsed_since(paused_at),
     }
   }
 }
 
+#[cfg(test)]
+impl Session {
+  /// A session that lasted its whole duration from the given start
+  pub fn finished(
+    session_type: SessionType,
+    minutes: u32,
+    start: OffsetDateTime,
+  ) -> Self {
+    let mut session = Session::new(session_type, minutes, None);
+    let end = start + time::Duration::minutes(minutes as i64);
+    session.start = SystemTime::from(start);
+    session.end = Some(SystemTime::from(end));
+    session
+  }
+
+  pub fn with_project(mut self, project_id: usize) -> Self {
+    self.project_id = Some(project_id);
+    self
+  }
+
+  pub fn with_task(mut self, task_id: usize) -> Self {
+    self.task_id = Some(task_id);
+    self
+  }
+}
+
+impl CalendarState {
+  pub fn get_rows_count(&self) -> usize {
+    match self.expanded {
+      Some(_) => self.sessions.len() + self.expanded_sessions.len(),
+      None => self.sessions.len(),
+    }
+  }
+
+  pub fn get_row(&self, index: usize) -> Option<CalendarRow> {
+    if index >= self.get_rows_count() {
+      return None;
+    }
+    match self.expanded {
+      Some(expanded) if index > expanded => {
+        let session = index - expanded - 1;
+        if session < self.expanded_sessions.len() {
+          Some(CalendarRow::Session(session))
+        } else {
+          Some(CalendarRow::Project(index - self.expanded_sessions.len()))
+        }
+      }
+      _ => Some(CalendarRow::Project(index)),
+    }
+  }
+
+  pub fn get_selected_row(&self) -> Option<CalendarRow> {
+    self.get_row(self.list_state.selected()?)
+  }
+}
+
+fn elapsed_since(time: SystemTime) -> Duration {
+  SystemTime::now().duration_since(time).unwrap_or_default()
+}
+
+/// A step of a project, with the work sessions spent on it
+#[derive(Debug, Clone)]
+pub struct Task {
+  pub id: usize,
+  pub project_id: usize,
+  pub name: String,
+  pub done: bool,
+  /// Pomodoros it should take
+  pub estimate: Option<u32>,
+  pub selected: bool,
+  pub work_sessions: u32,
+  pub time_spent: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct Project {
   pub id: usize,
@@ -133,4 +454,46 @@ pub struct Project {
   pub finished: bool,
   pub creation_date: String,
   pub modification_date: String,
+  pub finish_date: Option<String>,
+  /// Pomodoros it should take
+  pub estimate: Option<u32>,
+  /// In minutes, the options apply when missing
+  pub durations: ProjectDurations,
+  pub tags: Vec<String>,
+}
+
+/// Work, break and long break durations of a project
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProjectDurations {
+  pub work: Option<u32>,
+  pub short_break: Option<u32>,
+  pub long_break: Option<u32>,
+}
+
+impl Project {
+  pub fn is_over_estimate(&self) -> bool {
+    self
+      .estimate
+      .is_some_and(|estimate| self.work_sessions as u32 > estimate)
+  }
+}
+
+impl Task {
+  pub fn is_over_estimate(&self) -> bool {
+    self
+      .estimate
+      .is_some_and(|estimate| self.work_sessions > estimate)
+  }
+}
+
+/// How the work sessions of finished projects compare to their estimate
+#[derive(Debug, Default, PartialEq)]
+pub struct EstimateAccuracy {
+  pub projects: usize,
+  /// Finished in as many work sessions as estimated, or less
+  pub within_estimate: usize,
+  /// Work sessions done for 100 estimated
+  pub done_percent: u32,
+  /// Average gap between done and estimated, in percent of the estimate
+  pub average_gap: u32,
 }
@@ -1,11 +1,18 @@
+use crate::structs::{EstimateAccuracy, Project, ProjectDurations};
 use notify_rust::Notification;
 use ratatui::layout::{Constraint, Flex, Layout, Rect};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
-pub fn render_timer(start: SystemTime, duration: u32) -> Option<u32> {
+pub fn render_timer(
+  start: SystemTime,
+  duration: u32,
+  paused: Duration,
+) -> Option<u32> {
   let duration_secs = duration * 60;
-  let time = SystemTime::now().duration_since(start).unwrap().as_secs() as u32;
-  let countdown_secs = duration_secs - time;
+  let elapsed = SystemTime::now().duration_since(start).unwrap();
+  let time = elapsed.saturating_sub(paused).as_secs() as u32;
+  let countdown_secs = duration_secs.saturating_sub(time);
 
   if countdown_secs < 1 {
     return None;
@@ -14,8 +21,12 @@ pub fn render_timer(start: SystemTime, duration: u32) -> Option<u32> {
   Some(countdown_secs)
 }
 
-pub fn render_timer_str(start: SystemTime, duration: u32) -> Option<String> {
-  match render_timer(start, duration) {
+pub fn render_timer_str(
+  start: SystemTime,
+  duration: u32,
+  paused: Duration,
+) -> Option<String> {
+  match render_timer(start, duration, paused) {
     None => return None,
     Some(seconds) => Some(render_timer_seconds(seconds)),
   }
@@ -36,14 +47,184 @@ pub fn render_timer_seconds(seconds: u32) -> String {
   return format!("{}m {}s", minutes, remaining_seconds);
 }
 
-pub fn get_spent_time(start: SystemTime, duration: u32) -> u32 {
+/// Time spent in a session, paused time left out
+pub fn get_spent_time(
+  start: SystemTime,
+  duration: u32,
+  paused: Duration,
+) -> u32 {
   let duration_secs = duration * 60;
-  match render_timer(start, duration) {
+  match render_timer(start, duration, paused) {
     None => duration_secs,
     Some(seconds) => duration_secs - seconds,
   }
 }
 
+/// Converts a time to the local offset it had at that moment, keeps UTC when
+/// the local offset can't be determined
+pub fn to_local_date_time(time: SystemTime) -> OffsetDateTime {
+  let date_time = OffsetDateTime::from(time);
+  match UtcOffset::local_offset_at(date_time) {
+    Ok(offset) => date_time.to_offset(offset),
+    Err(_) => date_time,
+  }
+}
+
+pub fn get_local_offset() -> UtcOffset {
+  UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC)
+}
+
+/// Offsets a place has had over time, so days on both sides of a DST change
+/// keep their own
+pub trait TimeZone {
+  fn offset_at(&self, instant: OffsetDateTime) -> UtcOffset;
+
+  /// The instant a local time stands for, the offset is looked up at a first
+  /// guess of it
+  fn assume(&self, date_time: PrimitiveDateTime) -> OffsetDateTime {
+    let before = self.offset_at(date_time.assume_utc());
+    let offset = self.offset_at(date_time.assume_offset(before));
+    date_time.assume_offset(offset)
+  }
+
+  fn to_local(&self, instant: OffsetDateTime) -> OffsetDateTime {
+    instant.to_offset(self.offset_at(instant))
+  }
+}
+
+impl<T: TimeZone> TimeZone for &T {
+  fn offset_at(&self, instant: OffsetDateTime) -> UtcOffset {
+    (*self).offset_at(instant)
+  }
+}
+
+/// A single offset the whole year
+impl TimeZone for UtcOffset {
+  fn offset_at(&self, _: OffsetDateTime) -> UtcOffset {
+    *self
+  }
+}
+
+/// Time zone of the system, the current offset is used when the one of an
+/// instant can't be determined
+#[derive(Clone, Copy, Debug)]
+pub struct LocalZone;
+
+impl TimeZone for LocalZone {
+  fn offset_at(&self, instant: OffsetDateTime) -> UtcOffset {
+    UtcOffset::local_offset_at(instant).unwrap_or_else(|_| get_local_offset())
+  }
+}
+
+/// Monday and Sunday of the date's week
+pub fn get_week_bounds(date: Date) -> (Date, Date) {
+  let monday = date
+    - time::Duration::days(date.weekday().number_days_from_monday() as i64);
+  (monday, monday + time::Duration::days(6))
+}
+
+/// First and last days of the date's month
+pub fn get_month_bounds(date: Date) -> (Date, Date) {
+  let last_day = date.month().length(date.year());
+  (
+    date.replace_day(1).unwrap(),
+    date.replace_day(last_day).unwrap(),
+  )
+}
+
+/// Heatmap level of a day, from 0 without focus to 4 once the threshold in
+/// minutes is reached
+pub fn get_heat_level(duration: u32, threshold: u32) -> usize {
+  if duration == 0 {
+    return 0;
+  }
+  match duration
+    .saturating_mul(3)
+    .checked_div(threshold.saturating_mul(60))
+  {
+    None => 4,
+    Some(level) => 1 + level.min(3) as usize,
+  }
+}
+
+/// Work sessions done, over the estimate when there's one
+pub fn render_estimate(done: u32, estimate: Option<u32>) -> String {
+  match estimate {
+    Some(estimate) => format!("{}/{}", done, estimate),
+    None => done.to_string(),
+  }
+}
+
+/// An empty input removes the estimate
+pub fn parse_estimate(input: &str) -> Result<Option<u32>, &'static str> {
+  match input.trim() {
+    "" => Ok(None),
+    input => match input.parse::<u32>() {
+      Ok(estimate) if estimate > 0 => Ok(Some(estimate)),
+      _ => Err("The estimate is a number of pomodoros"),
+    },
+  }
+}
+
+/// Work, break and long break minutes as "50/10/20", missing ones left empty
+pub fn render_durations(durations: ProjectDurations) -> String {
+  if durations == ProjectDurations::default() {
+    return String::new();
+  }
+  [durations.work, durations.short_break, durations.long_break]
+    .map(|duration| duration.map(|d| d.to_string()).unwrap_or_default())
+    .join("/")
+}
+
+/// Reads durations written like `render_durations`, an empty one falls back
+/// to the options
+pub fn parse_durations(input: &str) -> Result<ProjectDurations, &'static str> {
+  let mut durations = [None; 3];
+  let parts: Vec<&str> = input.split('/').map(str::trim).collect();
+  if parts.len() > 3 {
+    return Err("Expected work/break/long break minutes");
+  }
+  for (duration, part) in durations.iter_mut().zip(parts) {
+    if part.is_empty() {
+      continue;
+    }
+    match part.parse::<u32>() {
+      Ok(minutes) if minutes > 0 => *duration = Some(minutes),
+      _ => return Err("Durations are numbers of minutes"),
+    }
+  }
+  let [work, short_break, long_break] = durations;
+  Ok(ProjectDurations {
+    work,
+    short_break,
+    long_break,
+  })
+}
+
+/// Projects without estimate are left out
+pub fn get_estimate_accuracy(projects: &[Project]) -> EstimateAccuracy {
+  let mut accuracy = EstimateAccuracy::default();
+  let (mut done, mut estimated, mut gaps) = (0, 0, 0);
+  for project in projects {
+    let Some(estimate) = project.estimate.filter(|estimate| *estimate > 0)
+    else {
+      continue;
+    };
+    let sessions = project.work_sessions.max(0) as u32;
+    accuracy.projects += 1;
+    if sessions <= estimate {
+      accuracy.within_estimate += 1;
+    }
+    done += sessions;
+    estimated += estimate;
+    gaps += sessions.abs_diff(estimate) * 100 / estimate;
+  }
+  accuracy.done_percent = (done * 100).checked_div(estimated).unwrap_or(0);
+  accuracy.average_gap =
+    gaps.checked_div(accuracy.projects as u32).unwrap_or(0);
+  accuracy
+}
+
 pub fn center(
   area: Rect,
   horizontal: Constraint,
@@ -129,7 +310,106 @@ mod tests {
   #[test]
   fn test_render_timer() {
     let start = SystemTime::now();
-    assert_eq!(render_timer_str(start, 1), Some("1m 0s".to_string()));
-    assert_eq!(render_timer_str(start, 3), Some("3m 0s".to_string()));
+    let paused = Duration::ZERO;
+    assert_eq!(
+      render_timer_str(start, 1, paused),
+      Some("1m 0s".to_string())
+    );
+    assert_eq!(
+      render_timer_str(start, 3, paused),
+      Some("3m 0s".to_string())
+    );
+  }
+
+  #[test]
+  fn test_spent_time_without_pauses() {
+    let start = SystemTime::now() - Duration::from_secs(600);
+    let paused = Duration::from_secs(240);
+    assert_eq!(
+      render_timer_str(start, 10, paused),
+      Some("4m 0s".to_string())
+    );
+    assert_eq!(get_spent_time(start, 10, paused), 360);
+    assert_eq!(get_spent_time(start, 10, Duration::ZERO), 600);
+  }
+
+  #[test]
+  fn test_heat_level() {
+    assert_eq!(get_heat_level(0, 120), 0);
+    assert_eq!(get_heat_level(60, 120), 1);
+    assert_eq!(get_heat_level(40 * 60, 120), 2);
+    assert_eq!(get_heat_level(119 * 60, 120), 3);
+    assert_eq!(get_heat_level(120 * 60, 120), 4);
+    assert_eq!(get_heat_level(600 * 60, 120), 4);
+    assert_eq!(get_heat_level(60, 0), 4);
+    assert_eq!(get_heat_level(60, u32::MAX), 1);
+    assert_eq!(get_heat_level(u32::MAX, 120), 4);
+  }
+
+  #[test]
+  fn test_period_bounds() {
+    use time::macros::date;
+
+    assert_eq!(
+      get_week_bounds(date!(2025 - 03 - 12)),
+      (date!(2025 - 03 - 10), date!(2025 - 03 - 16))
+    );
+    assert_eq!(
+      get_week_bounds(date!(2025 - 03 - 10)),
+      (date!(2025 - 03 - 10), date!(2025 - 03 - 16))
+    );
+    assert_eq!(
+      get_month_bounds(date!(2024 - 02 - 10)),
+      (date!(2024 - 02 - 01), date!(2024 - 02 - 29))
+    );
+  }
+
+  #[test]
+  fn test_estimate_accuracy() {
+    let project = |work_sessions, estimate| Project {
+      id: 1,
+      name: String::from("Tomato"),
+      selected: false,
+      time_spent: 0,
+      work_sessions,
+      finished: true,
+      creation_date: String::new(),
+      modification_date: String::new(),
+      finish_date: None,
+      estimate,
+      durations: ProjectDurations::default(),
+      tags: vec![],
+    };
+    let projects =
+      [project(12, Some(10)), project(4, Some(5)), project(3, None)];
+    assert_eq!(
+      get_estimate_accuracy(&projects),
+      EstimateAccuracy {
+        projects: 2,
+        within_estimate: 1,
+        done_percent: 106,
+        average_gap: 20,
+      }
+    );
+    assert_eq!(get_estimate_accuracy(&[]), EstimateAccuracy::default());
+    assert_eq!(parse_estimate(" 8 "), Ok(Some(8)));
+    assert_eq!(parse_estimate(""), Ok(None));
+    assert!(parse_estimate("0").is_err());
+  }
+
+  #[test]
+  fn test_project_durations() {
+    let durations = ProjectDurations {
+      work: Some(50),
+      short_break: Some(10),
+      long_break: None,
+    };
+    assert_eq!(render_durations(durations), "50/10/");
+    assert_eq!(parse_durations("50/10/"), Ok(durations));
+    assert_eq!(parse_durations(" 50 / 10 "), Ok(durations));
+    assert_eq!(render_durations(ProjectDurations::default()), "");
+    assert_eq!(parse_durations(""), Ok(ProjectDurations::default()));
+    assert!(parse_durations("50/10/20/5").is_err());
+    assert!(parse_durations("50/ten").is_err());
   }
 }
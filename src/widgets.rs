@@ -2,52 +2,86 @@ use ratatui::{
   buffer::Buffer,
   layout::{
     Alignment,
-    Constraint::{Fill, Length, Percentage},
-    Rect,
+    Constraint::{Fill, Length, Max, Percentage},
+    Flex, Rect,
   },
   prelude::{Direction, Layout},
   style::{Color, Modifier, Style, Stylize},
   symbols::border,
-  text::Line,
+  text::{Line, Span},
   widgets::{
-    Block, List, ListItem, ListState, Padding, Paragraph, StatefulWidget,
-    Widget,
+    Bar, BarChart, BarGroup, Block, List, ListItem, ListState, Padding,
+    Paragraph, StatefulWidget, Widget,
     block::{Position, Title},
     calendar::{CalendarEventStore, Monthly},
   },
 };
 use time::Date;
+use time::macros::format_description;
 
+use crate::app::report::get_period_bounds;
 use crate::structs::{
-  App, CalendarSection, Project, SessionPerDay, SessionType, State,
+  App, CalendarSection, DaySummary, DayTotal, Project, ProjectDeletion,
+  ProjectDurations, ReportPeriod, ReportState, SessionPerDay, SessionRecord,
+  SessionType, State, Task,
 };
 use crate::utils::{
-  break_line, center, convert_bool_to_string, render_timer_seconds, truncate,
+  LocalZone, TimeZone, break_line, center, convert_bool_to_string,
+  get_estimate_accuracy, get_heat_level, get_week_bounds, render_durations,
+  render_estimate, render_timer_seconds, truncate,
 };
 use crate::{app::options::Options, utils::notify};
 
 const SELECTED_STYLE: Style = Style::new().bg(Color::LightRed);
+const PROJECT_COLORS: [Color; 6] = [
+  Color::Cyan,
+  Color::Magenta,
+  Color::Yellow,
+  Color::Blue,
+  Color::Green,
+  Color::Red,
+];
+/// From the least to the most focused days
+const HEAT_COLORS: [Color; 4] = [
+  Color::Indexed(22),
+  Color::Indexed(28),
+  Color::Indexed(34),
+  Color::Indexed(40),
+];
 
 pub struct CounterWidget {
   pub time: String,
   pub session_type: SessionType,
+  pub paused: bool,
+  pub cycle: Option<String>,
 }
 
 impl Widget for CounterWidget {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let session_type = if let SessionType::Work = self.session_type {
-      " Work Session "
-    } else {
-      " Break Session "
+    let session_type = match self.session_type {
+      SessionType::Work => " Work Session ",
+      SessionType::Break => " Break Session ",
+      SessionType::LongBreak => " Long Break ",
     };
     let title = Title::from(session_type.bold());
-    let block = Block::bordered()
+    let mut block = Block::bordered()
       .title(title.alignment(Alignment::Center))
       .padding(Padding::new(1, 1, 1, 1));
-    let counter_area = center(area, Length(25), Length(5));
+    if let Some(cycle) = self.cycle {
+      block = block.title(
+        Title::from(format!(" {} ", cycle))
+          .alignment(Alignment::Right)
+          .position(Position::Bottom),
+      );
+    }
+    let mut lines = vec![Line::from(format!("Time: {}", self.time))];
+    if self.paused {
+      lines.push(Line::from("Paused".yellow().bold()));
+    }
+    let height = 4 + lines.len() as u16;
+    let counter_area = center(area, Length(25), Length(height));
 
-    let time = format!("Time: {}", self.time);
-    Paragraph::new(time)
+    Paragraph::new(lines)
       .centered()
       .block(block)
       .render(counter_area, buf);
@@ -58,20 +92,68 @@ pub struct ProjectsListWidget<'a> {
   pub projects: &'a [Project],
   pub selected_id: Option<usize>,
   pub state: &'a mut ListState,
+  /// Only choosing a project for a session
+  pub picker: bool,
+  /// The projects are the finished ones, shown with their finish date
+  pub finished: bool,
+  pub tag_filter: Option<&'a str>,
+  /// Tasks of the highlighted project, left out of the picker
+  pub tasks: &'a [Task],
+  /// Highlighted task when moving through the tasks
+  pub task_index: Option<usize>,
+  pub selected_task_id: Option<usize>,
 }
 
 impl Widget for ProjectsListWidget<'_> {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let session_type = " Projects ";
+    let session_type = if self.finished {
+      with_tag("Finished Projects", self.tag_filter)
+    } else {
+      with_tag("Projects", self.tag_filter)
+    };
     let title = Title::from(session_type.bold());
-    let instructions = Title::from(Line::from(vec![
-      " <A>".blue().bold(),
-      " Add ".into(),
-      "<F>".blue().bold(),
-      " Finished ".into(),
-      "<U>".blue().bold(),
-      " Update ".into(),
-    ]));
+    let instructions = if self.picker {
+      Title::from(Line::from(vec![
+        " <Enter>".blue().bold(),
+        " Choose ".into(),
+        "<N>".blue().bold(),
+        " No Project ".into(),
+      ]))
+    } else if self.finished {
+      Title::from(Line::from(vec![
+        " <F>".blue().bold(),
+        " Unfinish ".into(),
+        "<U>".blue().bold(),
+        " Update ".into(),
+        "<D>".blue().bold(),
+        " Delete ".into(),
+        "<E>".blue().bold(),
+        " Estimate ".into(),
+        "<Tab>".blue().bold(),
+        " In Progress ".into(),
+      ]))
+    } else {
+      Title::from(Line::from(vec![
+        " <A>".blue().bold(),
+        " Add ".into(),
+        "<F>".blue().bold(),
+        " Finished ".into(),
+        "<U>".blue().bold(),
+        " Update ".into(),
+        "<D>".blue().bold(),
+        " Delete ".into(),
+        "<T>".blue().bold(),
+        " Tags ".into(),
+        "<E>".blue().bold(),
+        " Estimate ".into(),
+        "<O>".blue().bold(),
+        " Durations ".into(),
+        "<#>".blue().bold(),
+        " Filter ".into(),
+        "<Tab>".blue().bold(),
+        " Archive ".into(),
+      ]))
+    };
     let block = Block::bordered()
       .title(title.alignment(Alignment::Center))
       .title(
@@ -86,6 +168,16 @@ impl Widget for ProjectsListWidget<'_> {
       None => 0,
     };
 
+    let width = self
+      .projects
+      .iter()
+      .map(|project| {
+        let tags: usize =
+          project.tags.iter().map(|tag| tag.chars().count() + 2).sum();
+        project.name.chars().count() + tags + 2
+      })
+      .max()
+      .unwrap_or_default();
     let projects: Vec<ListItem> = self
       .projects
       .iter()
@@ -98,18 +190,141 @@ impl Widget for ProjectsListWidget<'_> {
 
         let is_current = highlighted_index == i;
         let pre_content = if is_selected { "> " } else { "" };
-        let content = pre_content.to_string() + &project.name.clone();
+        let mut content = pre_content.to_string() + &project.name.clone();
+        for tag in &project.tags {
+          content = format!("{} #{}", content, tag);
+        }
+        let mut line = Line::from(vec![
+          format!("{:<width$}  ", content).into(),
+          render_progress(
+            project.work_sessions as u32,
+            project.estimate,
+            project.is_over_estimate(),
+          ),
+        ]);
+        if self.finished {
+          let date = project.finish_date.as_deref().unwrap_or_default();
+          let date = date.get(..10).unwrap_or(date);
+          line.push_span(format!("  {}", date));
+        } else if project.durations != ProjectDurations::default() {
+          let durations = render_durations(project.durations);
+          line.push_span(format!("  ⏱ {}", durations));
+        }
         if is_current {
-          return ListItem::from(content).style(SELECTED_STYLE);
+          return ListItem::from(line).style(SELECTED_STYLE);
         }
 
-        ListItem::from(content)
+        ListItem::from(line)
       })
       .collect();
 
     let list = List::new(projects).block(block);
-    let list_area = center(area, Length(100), Length(10));
+    if self.picker {
+      let list_area = center(area, Length(110), Length(10));
+      StatefulWidget::render(list, list_area, buf, self.state);
+      return;
+    }
+
+    let [list_area, tasks_area] = Layout::vertical([Length(10), Length(10)])
+      .areas(center(area, Length(110), Length(20)));
     StatefulWidget::render(list, list_area, buf, self.state);
+    TasksListWidget {
+      project: self.projects.get(highlighted_index),
+      tasks: self.tasks,
+      task_index: self.task_index,
+      selected_task_id: self.selected_task_id,
+    }
+    .render(tasks_area, buf);
+  }
+}
+
+pub struct TasksListWidget<'a> {
+  pub project: Option<&'a Project>,
+  pub tasks: &'a [Task],
+  pub task_index: Option<usize>,
+  pub selected_task_id: Option<usize>,
+}
+
+impl Widget for TasksListWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let title = match self.project {
+      Some(project) => {
+        format!(" Tasks of {} ", truncate(project.name.clone(), 40))
+      }
+      None => String::from(" Tasks "),
+    };
+    let instructions = match self.task_index {
+      Some(_) => Title::from(Line::from(vec![
+        " <A>".blue().bold(),
+        " Add ".into(),
+        "<U>".blue().bold(),
+        " Update ".into(),
+        "<E>".blue().bold(),
+        " Estimate ".into(),
+        "<X>".blue().bold(),
+        " Done ".into(),
+        "<Space>".blue().bold(),
+        " Select ".into(),
+        "<H>".blue().bold(),
+        " Projects ".into(),
+      ])),
+      None => {
+        Title::from(Line::from(vec![" <L>".blue().bold(), " Tasks ".into()]))
+      }
+    };
+    let block = Block::bordered()
+      .title(Title::from(title).alignment(Alignment::Center))
+      .title(
+        instructions
+          .alignment(Alignment::Center)
+          .position(Position::Bottom),
+      )
+      .padding(Padding::new(1, 1, 0, 0));
+
+    if self.tasks.is_empty() {
+      Paragraph::new("No task yet")
+        .centered()
+        .block(block)
+        .render(area, buf);
+      return;
+    }
+
+    let tasks: Vec<ListItem> = self
+      .tasks
+      .iter()
+      .enumerate()
+      .map(|(i, task)| {
+        let pre_content = if self.selected_task_id == Some(task.id) {
+          "> "
+        } else {
+          ""
+        };
+        let check = if task.done { "[x]" } else { "[ ]" };
+        let line = Line::from(vec![
+          format!("{}{} {}  ", pre_content, check, task.name).into(),
+          render_progress(
+            task.work_sessions,
+            task.estimate,
+            task.is_over_estimate(),
+          ),
+        ]);
+        if self.task_index == Some(i) {
+          return ListItem::from(line).style(SELECTED_STYLE);
+        }
+        if task.done {
+          return ListItem::from(line).dim();
+        }
+        ListItem::from(line)
+      })
+      .collect();
+
+    let mut state = ListState::default().with_selected(self.task_index);
+    StatefulWidget::render(
+      List::new(tasks).block(block),
+      area,
+      buf,
+      &mut state,
+    );
   }
 }
 
@@ -166,24 +381,103 @@ impl Widget for ConfirmWidget {
   }
 }
 
+pub struct DeleteProjectWidget<'a> {
+  pub deletion: &'a ProjectDeletion,
+}
+
+impl Widget for DeleteProjectWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let title = Title::from(" Delete Project ".bold());
+    let block = Block::bordered()
+      .title(title.alignment(Alignment::Center))
+      .padding(Padding::new(1, 1, 1, 1));
+    let delete_area = center(area, Length(45), Length(8));
+    let lost = match self.deletion.sessions {
+      0 => String::from("No session will be lost"),
+      sessions => format!(
+        "{} sessions and {} will be lost",
+        sessions,
+        render_timer_seconds(self.deletion.duration)
+      ),
+    };
+
+    Paragraph::new(vec![
+      Line::from(self.deletion.name.as_str()),
+      Line::from(lost),
+      Line::from(""),
+      Line::from("(y)es  (r)eassign sessions  (n)o"),
+    ])
+    .centered()
+    .block(block)
+    .render(delete_area, buf)
+  }
+}
+
+pub struct RecoverWidget {
+  pub session_type: SessionType,
+  pub time: Option<String>,
+}
+
+impl Widget for RecoverWidget {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let session_type = match self.session_type {
+      SessionType::Work => "Work session",
+      SessionType::Break => "Break",
+      SessionType::LongBreak => "Long break",
+    };
+    let title = Title::from(" Unfinished Session ".bold());
+    let block = Block::bordered()
+      .title(title.alignment(Alignment::Center))
+      .padding(Padding::new(1, 1, 1, 1));
+    let recover_area = center(area, Length(35), Length(8));
+    let time_left = match self.time {
+      Some(time) => format!("{} left", time),
+      None => String::from("Time is up"),
+    };
+
+    Paragraph::new(vec![
+      Line::from(format!("{} still running", session_type)),
+      Line::from(time_left),
+      Line::from(""),
+      Line::from("(r)esume  (s)ave  (d)iscard"),
+    ])
+    .centered()
+    .block(block)
+    .render(recover_area, buf)
+  }
+}
+
 pub struct CalendarWidget<'a> {
   pub selected_date: Date,
   pub sessions: &'a [SessionPerDay],
+  pub summary: &'a DaySummary,
+  pub month_totals: &'a [DayTotal],
+  pub week_totals: &'a [SessionPerDay],
+  pub expanded: Option<usize>,
+  pub expanded_sessions: &'a [SessionRecord],
+  /// Minutes of focus for the strongest color
+  pub heatmap_threshold: u32,
   pub selected_section: &'a CalendarSection,
   pub list_state: &'a mut ListState,
+  pub tag_filter: Option<&'a str>,
 }
 
 impl Widget for CalendarWidget<'_> {
   fn render(self, area: Rect, buf: &mut Buffer) {
-    let layout_area = center(area, Length(50), Percentage(80));
+    let [layout_area, _, chart_area] =
+      Layout::horizontal([Length(50), Length(2), Max(60)])
+        .flex(Flex::Center)
+        .areas(center(area, Percentage(100), Percentage(80)));
     let layout = Layout::default()
       .direction(Direction::Vertical)
-      .constraints(vec![Length(8), Fill(1)])
+      .constraints(vec![Length(8), Length(1), Length(2), Fill(1)])
       .split(layout_area);
     let cal_layout = center(layout[0], Length(25), Percentage(100));
-    let sessions_layout = layout[1];
+    let legend_layout = layout[1];
+    let summary_layout = layout[2];
+    let sessions_layout = layout[3];
 
-    let sessions_title = Title::from(" Sessions ");
+    let sessions_title = Title::from(with_tag("Sessions", self.tag_filter));
     let select_instruction = match self.selected_section {
       CalendarSection::Calendar => " Select List ",
       CalendarSection::List => " Select Calendar ",
@@ -191,6 +485,12 @@ impl Widget for CalendarWidget<'_> {
     let instructions = Title::from(Line::from(vec![
       " <Tab>".blue().bold(),
       select_instruction.into(),
+      "<Enter>".blue().bold(),
+      " Details ".into(),
+      "<a>".blue().bold(),
+      " Add ".into(),
+      "<e>".blue().bold(),
+      " Export ".into(),
     ]));
     let sessions_block = Block::bordered()
       .title(sessions_title.alignment(Alignment::Center))
@@ -207,7 +507,23 @@ impl Widget for CalendarWidget<'_> {
     };
 
     let mut cal_event = CalendarEventStore::default();
+    for day in self.month_totals {
+      let level = get_heat_level(day.duration, self.heatmap_threshold);
+      if level > 0 {
+        cal_event.add(day.date, Style::default().bg(HEAT_COLORS[level - 1]));
+      }
+    }
     cal_event.add(self.selected_date, Style::default().bg(cal_selected_color));
+
+    let mut legend = vec![Span::from("Less ")];
+    for color in HEAT_COLORS {
+      legend.push(Span::from("  ").bg(color));
+      legend.push(Span::from(" "));
+    }
+    legend.push(Span::from(format!(
+      "{}+",
+      render_timer_seconds(self.heatmap_threshold * 60)
+    )));
     let default_style = Style::default().add_modifier(Modifier::BOLD);
     let header_style = Style::default()
       .add_modifier(Modifier::BOLD)
@@ -230,14 +546,19 @@ impl Widget for CalendarWidget<'_> {
       Some(index) => index,
       None => 0,
     };
-    let sessions_list: Vec<ListItem> = self
-      .sessions
-      .iter()
+    let mut contents = vec![];
+    for (i, session) in self.sessions.iter().enumerate() {
+      let timer = render_timer_seconds(session.duration);
+      contents.push(format!("{} - {}", session.project_name, timer));
+      if self.expanded == Some(i) {
+        contents.extend(self.expanded_sessions.iter().map(format_session));
+      }
+    }
+    let sessions_list: Vec<ListItem> = contents
+      .into_iter()
       .enumerate()
-      .map(|(i, session)| {
+      .map(|(i, content)| {
         let is_current = highlighted_index == i;
-        let timer = render_timer_seconds(session.duration);
-        let content = format!("{} - {}", session.project_name, timer);
         let wrapped = break_line(content, (sessions_layout.width - 4) as usize);
 
         if is_current && let CalendarSection::List = self.selected_section {
@@ -250,8 +571,366 @@ impl Widget for CalendarWidget<'_> {
 
     let list = List::new(sessions_list).block(sessions_block);
 
+    let summary = self.summary;
+    let total_time = summary.focus_time + summary.break_time;
+    let summary_lines = match (summary.focus_time * 100).checked_div(total_time)
+    {
+      None => vec![Line::from("No session")],
+      Some(focus_ratio) => vec![
+        Line::from(format!(
+          "Focus {} ({}%) - Break {}",
+          render_timer_seconds(summary.focus_time),
+          focus_ratio,
+          render_timer_seconds(summary.break_time),
+        )),
+        Line::from(format!(
+          "{}/{} work sessions completed",
+          summary.completed_sessions, summary.work_sessions
+        )),
+      ],
+    };
+
     cal.render(cal_layout, buf);
+    Line::from(legend).centered().render(legend_layout, buf);
+    Paragraph::new(summary_lines)
+      .centered()
+      .render(summary_layout, buf);
     StatefulWidget::render(list, sessions_layout, buf, self.list_state);
+    WeekChartWidget {
+      selected_date: self.selected_date,
+      totals: self.week_totals,
+      tag_filter: self.tag_filter,
+    }
+    .render(chart_area, buf);
+  }
+}
+
+/// Block title telling which tag the content is limited to
+fn with_tag(title: &str, tag: Option<&str>) -> String {
+  match tag {
+    Some(tag) => format!(" {} #{} ", title, tag),
+    None => format!(" {} ", title),
+  }
+}
+
+/// Work sessions against the estimate, in red once over it
+fn render_progress(
+  done: u32,
+  estimate: Option<u32>,
+  over: bool,
+) -> Span<'static> {
+  let progress = format!("🍅 {:<7}", render_estimate(done, estimate));
+  if over {
+    return progress.red().bold();
+  }
+  Span::from(progress)
+}
+
+/// A session of an expanded entry of the calendar list
+fn format_session(session: &SessionRecord) -> String {
+  let format = format_description!("[hour]:[minute]");
+  let start = LocalZone.to_local(session.start_date);
+  let session_type = match session.session_type {
+    SessionType::Work => "Work",
+    SessionType::Break => "Break",
+    SessionType::LongBreak => "Long break",
+  };
+
+  let task = match &session.task_name {
+    Some(name) => format!(" ({})", name),
+    None => String::new(),
+  };
+
+  format!(
+    "  {} {}{} - {}",
+    start.format(&format).unwrap_or_default(),
+    session_type,
+    task,
+    render_timer_seconds(session.duration)
+  )
+}
+
+/// Focus minutes of each day of the selected date's week, a bar per project
+pub struct WeekChartWidget<'a> {
+  pub selected_date: Date,
+  pub totals: &'a [SessionPerDay],
+  pub tag_filter: Option<&'a str>,
+}
+
+impl Widget for WeekChartWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let block = Block::bordered()
+      .title(
+        Title::from(with_tag("Week Focus (minutes)", self.tag_filter))
+          .alignment(Alignment::Center),
+      )
+      .padding(Padding::new(1, 1, 1, 0));
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    // Projects keep their color, and their place in each day, all week long
+    let mut projects: Vec<(Option<usize>, &str)> = vec![];
+    for total in self.totals {
+      if !projects.iter().any(|(id, _)| *id == total.project_id) {
+        projects.push((total.project_id, &total.project_name));
+      }
+    }
+    let legend_rows = 4;
+    let legend_height = projects.len().min(legend_rows) as u16;
+    let [chart_area, legend_area] =
+      Layout::vertical([Fill(1), Length(legend_height)]).areas(inner);
+
+    let group_gap = 1;
+    let group_width = inner.width.saturating_sub(6 * group_gap) / 7;
+    let bar_width = (group_width / projects.len().max(1) as u16).max(1);
+    let (monday, _) = get_week_bounds(self.selected_date);
+    let mut chart = BarChart::default()
+      .bar_width(bar_width)
+      .bar_gap(0)
+      .group_gap(group_gap);
+
+    for offset in 0..7 {
+      let date = monday + time::Duration::days(offset);
+      let bars: Vec<Bar> = projects
+        .iter()
+        .enumerate()
+        .map(|(index, (project_id, _))| {
+          let seconds = self
+            .totals
+            .iter()
+            .find(|total| total.date == date && total.project_id == *project_id)
+            .map_or(0, |total| total.duration);
+          let minutes = seconds / 60;
+          let color = get_project_color(index);
+          // Minutes are written inside the bars when they fit
+          let text = match bar_width {
+            3.. if minutes > 0 => minutes.to_string(),
+            _ => String::new(),
+          };
+          Bar::default()
+            .value(minutes as u64)
+            .text_value(text)
+            .style(Style::default().fg(color))
+            .value_style(Style::default().fg(Color::Black).bg(color))
+        })
+        .collect();
+      let mut label = Line::from(date.weekday().to_string()[..3].to_string());
+      if date == self.selected_date {
+        label = label.bold().red();
+      }
+      chart = chart.data(BarGroup::default().label(label).bars(&bars));
+    }
+
+    // The legend wraps in columns, the projects without room left are
+    // counted on its last line
+    let capacity = (legend_area.width / 16).max(1) as usize * legend_rows;
+    let shown = if projects.len() > capacity {
+      capacity - 1
+    } else {
+      projects.len()
+    };
+    let mut legend: Vec<Line> = projects
+      .iter()
+      .take(shown)
+      .enumerate()
+      .map(|(index, (_, name))| {
+        Line::from(vec![
+          Span::from("  ").bg(get_project_color(index)),
+          Span::from(format!(" {}", name)),
+        ])
+      })
+      .collect();
+    if shown < projects.len() {
+      legend.push(Line::from(format!("   +{} more", projects.len() - shown)));
+    }
+
+    if projects.is_empty() {
+      Paragraph::new("No session this week")
+        .centered()
+        .render(chart_area, buf);
+    } else {
+      chart.render(chart_area, buf);
+    }
+    let columns = legend.len().div_ceil(legend_rows);
+    let column_areas =
+      Layout::horizontal(vec![Fill(1); columns]).split(legend_area);
+    for (lines, area) in legend.chunks(legend_rows).zip(column_areas.iter()) {
+      Paragraph::new(lines.to_vec()).render(*area, buf);
+    }
+  }
+}
+
+fn get_project_color(index: usize) -> Color {
+  PROJECT_COLORS[index % PROJECT_COLORS.len()]
+}
+
+pub struct ReportWidget<'a> {
+  pub report: &'a ReportState,
+}
+
+impl Widget for ReportWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let Some(date) = self.report.date else {
+      return;
+    };
+    let (from, to) = get_period_bounds(date, self.report.period);
+    let (title, switch_instruction) = match self.report.period {
+      ReportPeriod::Week => (format!("Week {} - {}", from, to), " Month "),
+      ReportPeriod::Month => {
+        (format!("{} {}", from.month(), from.year()), " Week ")
+      }
+    };
+    let title = with_tag(&title, self.report.tag_filter.as_deref());
+    let instructions = Title::from(Line::from(vec![
+      " <H/L>".blue().bold(),
+      " Prev/Next ".into(),
+      "<Tab>".blue().bold(),
+      switch_instruction.into(),
+      "<#>".blue().bold(),
+      " Tag ".into(),
+      "<E>".blue().bold(),
+      " Estimates ".into(),
+    ]));
+    let block = Block::bordered()
+      .title(Title::from(title.bold()).alignment(Alignment::Center))
+      .title(
+        instructions
+          .alignment(Alignment::Center)
+          .position(Position::Bottom),
+      )
+      .padding(Padding::new(1, 1, 1, 1));
+    let report_area = center(area, Length(70), Percentage(80));
+    let inner = block.inner(report_area);
+    block.render(report_area, buf);
+
+    let layout = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints(vec![Length(2), Fill(1)])
+      .split(inner);
+    let columns = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints(vec![Fill(1), Length(40)])
+      .split(layout[1]);
+
+    let total: u32 = self.report.projects.iter().map(|p| p.duration).sum();
+    let sessions: u32 = self.report.projects.iter().map(|p| p.sessions).sum();
+    let summary = match total.checked_div(sessions) {
+      None => String::from("No session"),
+      Some(average) => format!(
+        "Focus {} - {} sessions - {} on average",
+        render_timer_seconds(total),
+        sessions,
+        render_timer_seconds(average)
+      ),
+    };
+
+    let project_lines: Vec<Line> = self
+      .report
+      .projects
+      .iter()
+      .map(|project| {
+        Line::from(format!(
+          "{} - {} ({})",
+          truncate(project.project_name.clone(), 25),
+          render_timer_seconds(project.duration),
+          project.sessions
+        ))
+      })
+      .collect();
+    let day_lines: Vec<Line> = self
+      .report
+      .days
+      .iter()
+      .map(|day| {
+        let line = Line::from(format!(
+          "{} {:>2} - {}",
+          &day.date.weekday().to_string()[..3],
+          day.date.day(),
+          render_timer_seconds(day.duration)
+        ));
+        if day.sessions == 0 { line.dim() } else { line }
+      })
+      .collect();
+
+    Paragraph::new(summary).centered().render(layout[0], buf);
+    Paragraph::new(project_lines).render(columns[0], buf);
+    // A month doesn't always fit in one column
+    let rows = columns[1].height.max(1) as usize;
+    let day_columns = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints(vec![Fill(1); day_lines.len().div_ceil(rows)])
+      .split(columns[1]);
+    for (lines, column) in day_lines.chunks(rows).zip(day_columns.iter()) {
+      Paragraph::new(lines.to_vec()).render(*column, buf);
+    }
+  }
+}
+
+/// Finished projects next to their estimate
+pub struct EstimatesReportWidget<'a> {
+  pub projects: &'a [Project],
+  pub tag_filter: Option<&'a str>,
+}
+
+impl Widget for EstimatesReportWidget<'_> {
+  fn render(self, area: Rect, buf: &mut Buffer) {
+    let title = with_tag("Estimates of Finished Projects", self.tag_filter);
+    let instructions = Title::from(Line::from(vec![
+      " <#>".blue().bold(),
+      " Tag ".into(),
+      "<E>".blue().bold(),
+      " Period ".into(),
+    ]));
+    let block = Block::bordered()
+      .title(Title::from(title.bold()).alignment(Alignment::Center))
+      .title(
+        instructions
+          .alignment(Alignment::Center)
+          .position(Position::Bottom),
+      )
+      .padding(Padding::new(1, 1, 1, 1));
+    let report_area = center(area, Length(70), Percentage(80));
+    let inner = block.inner(report_area);
+    block.render(report_area, buf);
+
+    let [summary_area, projects_area] =
+      Layout::vertical([Length(2), Fill(1)]).areas(inner);
+    let accuracy = get_estimate_accuracy(self.projects);
+    let summary = if accuracy.projects == 0 {
+      String::from("No finished project with an estimate")
+    } else {
+      format!(
+        "{}/{} within estimate - {}% done overall - {}% off on average",
+        accuracy.within_estimate,
+        accuracy.projects,
+        accuracy.done_percent,
+        accuracy.average_gap
+      )
+    };
+
+    let project_lines: Vec<Line> = self
+      .projects
+      .iter()
+      .map(|project| {
+        let date = project.finish_date.as_deref().unwrap_or_default();
+        Line::from(vec![
+          format!(
+            "{} {:<30} ",
+            date.get(..10).unwrap_or(date),
+            truncate(project.name.clone(), 27)
+          )
+          .into(),
+          render_progress(
+            project.work_sessions as u32,
+            project.estimate,
+            project.is_over_estimate(),
+          ),
+        ])
+      })
+      .collect();
+
+    Paragraph::new(summary).centered().render(summary_area, buf);
+    Paragraph::new(project_lines).render(projects_area, buf);
   }
 }
 
@@ -267,16 +946,27 @@ impl Widget for &mut App {
 
     let main_cmd = match self.state {
       State::WorkInput | State::BreakInput => " <Enter>",
-      State::WorkDurationInput | State::BreakDurationInput => " <Enter>",
+      State::WorkDurationInput
+      | State::BreakDurationInput
+      | State::LongBreakDurationInput
+      | State::SessionsBeforeLongBreakInput
+      | State::HeatmapThresholdInput => " <Enter>",
       _ => " <Space>",
     };
+    let projects_cmd = match &self.current_session {
+      None => " Projects ",
+      Some(session) if session.is_paused() => " Resume ",
+      Some(_) => " Pause ",
+    };
     let instructions = Title::from(Line::from(vec![
       main_cmd.blue().bold(),
       toggle_session.into(),
       "<P>".blue().bold(),
-      " Projects ".into(),
+      projects_cmd.into(),
       "<C>".blue().bold(),
       " Calendar ".into(),
+      "<R>".blue().bold(),
+      " Report ".into(),
       "<O>".blue().bold(),
       " Options ".into(),
       "<Q>".blue().bold(),
@@ -285,12 +975,18 @@ impl Widget for &mut App {
     let selected_project = self.get_selected_project();
     let selected_project_name = match selected_project {
       None => String::from("None"),
-      Some(project) => project.name.clone(),
+      Some(project) => truncate(project.name.clone(), 25),
+    };
+    let selected_task_name = match &self.projects_list.selected_task {
+      None => String::new(),
+      Some(task) => format!(" · {}", truncate(task.name.clone(), 25)),
     };
-    let project_title =
-      Title::from(format!(" 📁 {} ", truncate(selected_project_name, 25)))
-        .alignment(Alignment::Right)
-        .position(Position::Top);
+    let project_title = Title::from(format!(
+      " 📁 {}{} ",
+      selected_project_name, selected_task_name
+    ))
+    .alignment(Alignment::Right)
+    .position(Position::Top);
     Block::bordered()
       .title(title.alignment(Alignment::Left))
       .title(project_title)
@@ -316,7 +1012,7 @@ impl Widget for OptionsWidget<'_> {
       " <Enter>".blue().bold(),
       " Update ".into(),
     ]));
-    let options_area = center(area, Length(50), Length(8));
+    let options_area = center(area, Length(50), Length(11));
 
     let lines = self.data.get_list();
     let names_lines: Vec<Line> = lines